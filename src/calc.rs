@@ -0,0 +1,265 @@
+//! Actual stat calculation from base stats, level, IVs, EVs and nature, using
+//! the formulas of Generation III onwards.
+
+use crate::models::Stat;
+
+/// PokeAPI names of the six stats, in the order IVs and EVs are stored.
+pub const STAT_NAMES: [&str; 6] = [
+    "hp",
+    "attack",
+    "defense",
+    "special-attack",
+    "special-defense",
+    "speed",
+];
+
+pub const MAX_LEVEL: u32 = 100;
+pub const MAX_IV: u32 = 31;
+pub const MAX_EV: u32 = 252;
+pub const MAX_EV_TOTAL: u32 = 510;
+
+/// The 25 natures in their index order. Nature `i` raises the `i / 5`-th and
+/// lowers the `i % 5`-th stat of `NATURE_STATS`; the five where both are the
+/// same have no effect.
+pub const NATURES: [&str; 25] = [
+    "Hardy", "Lonely", "Brave", "Adamant", "Naughty", "Bold", "Docile", "Relaxed", "Impish", "Lax",
+    "Timid", "Hasty", "Serious", "Jolly", "Naive", "Modest", "Mild", "Quiet", "Bashful", "Rash",
+    "Calm", "Gentle", "Sassy", "Careful", "Quirky",
+];
+
+/// Indices into `STAT_NAMES` of the stats natures act on, in nature order
+/// (Attack, Defense, Speed, Sp. Atk, Sp. Def).
+const NATURE_STATS: [usize; 5] = [1, 2, 5, 3, 4];
+
+/// Stats (indices into `STAT_NAMES`) raised and lowered by nature `nature`, or
+/// `None` for a neutral nature.
+pub fn nature_effect(nature: usize) -> Option<(usize, usize)> {
+    let up = NATURE_STATS[nature / 5 % 5];
+    let down = NATURE_STATS[nature % 5];
+    (up != down).then_some((up, down))
+}
+
+/// Actual stat at `level` for a `base` stat with the given IV (0-31), EV
+/// (0-252) and nature modifier in percent (90, 100 or 110; ignored for HP).
+pub fn stat_at(stat: &str, base: u32, level: u32, iv: u32, ev: u32, nature: u32) -> u32 {
+    let core = (2 * base + iv + ev / 4) * level / 100;
+    if stat == "hp" {
+        // Shedinja, the only Pokémon with base HP 1, always has 1 HP.
+        if base == 1 {
+            return 1;
+        }
+        core + level + 10
+    } else {
+        (core + 5) * nature / 100
+    }
+}
+
+/// Lowest and highest possible value of a stat at `level`: no IVs/EVs with a
+/// hindering nature, and 31 IVs / 252 EVs with a boosting nature.
+pub fn stat_range(stat: &str, base: u32, level: u32) -> (u32, u32) {
+    (
+        stat_at(stat, base, level, 0, 0, 90),
+        stat_at(stat, base, level, MAX_IV, MAX_EV, 110),
+    )
+}
+
+/// One editable value of a `StatBuild`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CalcField {
+    Level,
+    Nature,
+    /// IV of the stat at this index of `STAT_NAMES`.
+    Iv(usize),
+    Ev(usize),
+}
+
+impl CalcField {
+    /// Number of fields, for moving a cursor through them.
+    pub const COUNT: usize = 2 + 2 * STAT_NAMES.len();
+
+    /// Field at `index` in reading order: level, nature, then the IV and EV of
+    /// each stat.
+    pub fn from_index(index: usize) -> Self {
+        match index % Self::COUNT {
+            0 => CalcField::Level,
+            1 => CalcField::Nature,
+            i if i.is_multiple_of(2) => CalcField::Iv((i - 2) / 2),
+            i => CalcField::Ev((i - 2) / 2),
+        }
+    }
+}
+
+/// Level, nature, IVs and EVs of a Pokémon being built.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatBuild {
+    pub level: u32,
+    /// Index into `NATURES`.
+    pub nature: usize,
+    pub ivs: [u32; 6],
+    pub evs: [u32; 6],
+}
+
+impl Default for StatBuild {
+    fn default() -> Self {
+        StatBuild {
+            level: 50,
+            nature: 0,
+            ivs: [MAX_IV; 6],
+            evs: [0; 6],
+        }
+    }
+}
+
+impl StatBuild {
+    pub fn ev_total(&self) -> u32 {
+        self.evs.iter().sum()
+    }
+
+    /// Nature modifier in percent for the stat at index `i` of `STAT_NAMES`.
+    fn nature_percent(&self, i: usize) -> u32 {
+        match nature_effect(self.nature) {
+            Some((up, _)) if up == i => 110,
+            Some((_, down)) if down == i => 90,
+            _ => 100,
+        }
+    }
+
+    /// Actual value of `stat` with this build, or `None` for a stat outside
+    /// the main six.
+    pub fn calc(&self, stat: &Stat) -> Option<u32> {
+        let i = STAT_NAMES.iter().position(|n| *n == stat.name)?;
+        Some(stat_at(
+            &stat.name,
+            stat.base,
+            self.level,
+            self.ivs[i],
+            self.evs[i],
+            self.nature_percent(i),
+        ))
+    }
+
+    pub fn get(&self, field: CalcField) -> u32 {
+        match field {
+            CalcField::Level => self.level,
+            CalcField::Nature => self.nature as u32,
+            CalcField::Iv(i) => self.ivs[i],
+            CalcField::Ev(i) => self.evs[i],
+        }
+    }
+
+    /// Set `field` to `value`, clamped to its valid range. EVs are also capped
+    /// so the total stays within 510.
+    pub fn set(&mut self, field: CalcField, value: u32) {
+        match field {
+            CalcField::Level => self.level = value.clamp(1, MAX_LEVEL),
+            CalcField::Nature => self.nature = value as usize % NATURES.len(),
+            CalcField::Iv(i) => self.ivs[i] = value.min(MAX_IV),
+            CalcField::Ev(i) => {
+                let others = self.ev_total() - self.evs[i];
+                self.evs[i] = value.min(MAX_EV).min(MAX_EV_TOTAL - others);
+            }
+        }
+    }
+
+    /// Step `field` up or down by `delta` units. EVs move in steps of 4 (one
+    /// stat point at level 100) and natures wrap around.
+    pub fn adjust(&mut self, field: CalcField, delta: i32) {
+        let step = match field {
+            CalcField::Ev(_) => 4,
+            _ => 1,
+        };
+        let current = self.get(field) as i32;
+        let value = match field {
+            CalcField::Nature => (current + delta).rem_euclid(NATURES.len() as i32),
+            _ => (current + delta * step).max(0),
+        };
+        self.set(field, value as u32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nature(name: &str) -> usize {
+        NATURES.iter().position(|n| *n == name).unwrap()
+    }
+
+    /// Garchomp: 108 / 130 / 95 / 80 / 85 / 102.
+    fn garchomp() -> Vec<Stat> {
+        STAT_NAMES
+            .iter()
+            .zip([108, 130, 95, 80, 85, 102])
+            .map(|(name, base)| Stat {
+                name: name.to_string(),
+                base,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nature_table() {
+        assert_eq!(nature_effect(nature("Hardy")), None);
+        assert_eq!(nature_effect(nature("Serious")), None);
+        assert_eq!(nature_effect(nature("Adamant")), Some((1, 3)));
+        assert_eq!(nature_effect(nature("Jolly")), Some((5, 3)));
+        assert_eq!(nature_effect(nature("Timid")), Some((5, 1)));
+        assert_eq!(nature_effect(nature("Modest")), Some((3, 1)));
+        assert_eq!(nature_effect(nature("Bold")), Some((2, 1)));
+        assert_eq!(nature_effect(nature("Careful")), Some((4, 3)));
+        let neutral = (0..NATURES.len())
+            .filter(|&i| nature_effect(i).is_none())
+            .count();
+        assert_eq!(neutral, 5);
+    }
+
+    #[test]
+    fn jolly_garchomp_max_speed() {
+        let stats = garchomp();
+        let mut build = StatBuild {
+            level: 100,
+            nature: nature("Jolly"),
+            ivs: [MAX_IV; 6],
+            evs: [0, 252, 0, 0, 4, 252],
+        };
+        let calc = |b: &StatBuild| stats.iter().map(|s| b.calc(s).unwrap()).collect::<Vec<_>>();
+        assert_eq!(calc(&build), vec![357, 359, 226, 176, 207, 333]);
+        build.level = 50;
+        assert_eq!(calc(&build), vec![183, 182, 115, 90, 106, 169]);
+    }
+
+    #[test]
+    fn max_hp() {
+        assert_eq!(stat_at("hp", 108, 100, MAX_IV, MAX_EV, 100), 420);
+        assert_eq!(stat_at("hp", 108, 50, MAX_IV, MAX_EV, 100), 215);
+        // The nature never affects HP.
+        assert_eq!(stat_at("hp", 108, 100, MAX_IV, MAX_EV, 110), 420);
+        // Shedinja always has 1 HP.
+        assert_eq!(stat_at("hp", 1, 100, MAX_IV, MAX_EV, 100), 1);
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(stat_range("attack", 130, 100), (238, 394));
+        assert_eq!(stat_range("attack", 130, 50), (121, 200));
+        assert_eq!(stat_range("hp", 108, 100), (326, 420));
+        assert_eq!(stat_range("speed", 102, 50), (96, 169));
+    }
+
+    #[test]
+    fn evs_are_capped() {
+        let mut build = StatBuild::default();
+        build.set(CalcField::Ev(1), 300);
+        assert_eq!(build.evs[1], MAX_EV);
+        build.set(CalcField::Ev(5), 252);
+        build.set(CalcField::Ev(0), 252);
+        assert_eq!(build.evs[0], 6);
+        assert_eq!(build.ev_total(), MAX_EV_TOTAL);
+        build.adjust(CalcField::Ev(0), -1);
+        assert_eq!(build.evs[0], 2);
+        build.adjust(CalcField::Nature, -1);
+        assert_eq!(build.nature, NATURES.len() - 1);
+        build.set(CalcField::Level, 0);
+        assert_eq!(build.level, 1);
+    }
+}
@@ -0,0 +1,298 @@
+//! Persistent on-disk cache of resized sprite thumbnails.
+//!
+//! Thumbnails are stored at the sizes the sprite panel actually renders, keyed
+//! by a hash of the source PNG plus the size, so a changed sprite simply misses.
+//! The file is append-only:
+//!
+//! ```text
+//! "DXTC" version:u8  { hash:u64 w:u16 h:u16 len:u32 rgba[len] }*
+//! ```
+//!
+//! (all integers little-endian). Only the record index is read at startup;
+//! pixels are read on demand.
+//!
+//! Several instances may share the file: records are appended with
+//! `O_APPEND`, so they never overwrite each other, and `get` checks the
+//! record header before trusting an offset.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+pub const DISK_CACHE_PATH: &str = "data/thumbs.bin";
+
+const MAGIC: &[u8; 4] = b"DXTC";
+const VERSION: u8 = 1;
+const HEADER_LEN: u64 = 5;
+const RECORD_HEADER_LEN: u64 = 16;
+
+/// Start over instead of growing forever when stale entries pile up.
+const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// FNV-1a hash of a sprite file. Stable across Rust versions, unlike
+/// `DefaultHasher`, which matters for a cache that outlives the binary.
+pub fn sprite_hash(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+struct Inner {
+    file: File,
+    /// (hash, w, h) -> (offset of the record, pixel length)
+    index: HashMap<(u64, u32, u32), (u64, u32)>,
+}
+
+fn record_header(hash: u64, w: u32, h: u32, n: u32) -> [u8; RECORD_HEADER_LEN as usize] {
+    let mut rec = [0u8; RECORD_HEADER_LEN as usize];
+    rec[0..8].copy_from_slice(&hash.to_le_bytes());
+    rec[8..10].copy_from_slice(&(w as u16).to_le_bytes());
+    rec[10..12].copy_from_slice(&(h as u16).to_le_bytes());
+    rec[12..16].copy_from_slice(&n.to_le_bytes());
+    rec
+}
+
+pub struct DiskCache {
+    inner: Mutex<Inner>,
+}
+
+impl DiskCache {
+    /// Open (or create) the cache file at `path` and index its records. A
+    /// truncated trailing record, e.g. from a crash mid-write, is dropped.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let len = file.metadata()?.len();
+
+        let mut header = [0u8; HEADER_LEN as usize];
+        let valid_header = len >= HEADER_LEN
+            && file.read_exact(&mut header).is_ok()
+            && &header[..4] == MAGIC
+            && header[4] == VERSION;
+        if !valid_header || len > MAX_FILE_BYTES {
+            file.set_len(0)?;
+            let mut header = MAGIC.to_vec();
+            header.push(VERSION);
+            file.write_all(&header)?;
+            return Ok(DiskCache {
+                inner: Mutex::new(Inner {
+                    file,
+                    index: HashMap::new(),
+                }),
+            });
+        }
+
+        let mut index = HashMap::new();
+        let mut pos = HEADER_LEN;
+        {
+            let mut reader = BufReader::new(&mut file);
+            reader.seek(SeekFrom::Start(pos))?;
+            let mut rec = [0u8; RECORD_HEADER_LEN as usize];
+            while pos + RECORD_HEADER_LEN <= len && reader.read_exact(&mut rec).is_ok() {
+                let hash = u64::from_le_bytes(rec[0..8].try_into().unwrap());
+                let w = u16::from_le_bytes(rec[8..10].try_into().unwrap()) as u32;
+                let h = u16::from_le_bytes(rec[10..12].try_into().unwrap()) as u32;
+                let n = u32::from_le_bytes(rec[12..16].try_into().unwrap());
+                let data = pos + RECORD_HEADER_LEN;
+                if data + n as u64 > len {
+                    break;
+                }
+                index.insert((hash, w, h), (pos, n));
+                reader.seek_relative(n as i64)?;
+                pos = data + n as u64;
+            }
+        }
+        if pos < len {
+            file.set_len(pos)?;
+        }
+        Ok(DiskCache {
+            inner: Mutex::new(Inner { file, index }),
+        })
+    }
+
+    /// RGBA pixels of the `w` x `h` thumbnail of the sprite with `hash`. A
+    /// record that no longer has the expected header (the file was reset or
+    /// cut short by another instance) is forgotten.
+    pub fn get(&self, hash: u64, w: u32, h: u32) -> Option<Vec<u8>> {
+        let mut inner = self.inner.lock().unwrap();
+        let (offset, n) = *inner.index.get(&(hash, w, h))?;
+        let mut buf = vec![0u8; RECORD_HEADER_LEN as usize + n as usize];
+        let read = inner.file.seek(SeekFrom::Start(offset)).is_ok()
+            && inner.file.read_exact(&mut buf).is_ok();
+        if !read || buf[..RECORD_HEADER_LEN as usize] != record_header(hash, w, h, n) {
+            inner.index.remove(&(hash, w, h));
+            return None;
+        }
+        Some(buf.split_off(RECORD_HEADER_LEN as usize))
+    }
+
+    /// Append a thumbnail. Errors are ignored: the cache is an optimisation.
+    pub fn put(&self, hash: u64, w: u32, h: u32, pixels: &[u8]) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.index.contains_key(&(hash, w, h)) || w > u16::MAX as u32 || h > u16::MAX as u32 {
+            return;
+        }
+        let n = pixels.len() as u32;
+        let mut rec = Vec::with_capacity(RECORD_HEADER_LEN as usize + pixels.len());
+        rec.extend_from_slice(&record_header(hash, w, h, n));
+        rec.extend_from_slice(pixels);
+        // In append mode the write lands at the current end of the file, which
+        // another instance may have moved; the position after it tells where.
+        if inner.file.write_all(&rec).is_err() {
+            return;
+        }
+        if let Ok(end) = inner.file.stream_position() {
+            inner
+                .index
+                .insert((hash, w, h), (end - rec.len() as u64, n));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A fresh cache file path in the temp dir, unique to this test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "dextui-diskcache-{}-{}.bin",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn pixels(seed: u8, len: usize) -> Vec<u8> {
+        (0..len).map(|i| seed.wrapping_add(i as u8)).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let cache = DiskCache::open(&path).unwrap();
+        cache.put(1, 2, 2, &pixels(1, 16));
+        cache.put(1, 4, 4, &pixels(2, 64));
+        assert_eq!(cache.get(1, 2, 2), Some(pixels(1, 16)));
+        assert_eq!(cache.get(1, 4, 4), Some(pixels(2, 64)));
+        assert_eq!(cache.get(2, 2, 2), None);
+        // A second put of the same key is ignored.
+        cache.put(1, 2, 2, &pixels(9, 16));
+        assert_eq!(cache.get(1, 2, 2), Some(pixels(1, 16)));
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            HEADER_LEN + 2 * RECORD_HEADER_LEN + 16 + 64
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn reopen() {
+        let path = temp_path("reopen");
+        {
+            let cache = DiskCache::open(&path).unwrap();
+            cache.put(7, 2, 2, &pixels(7, 16));
+            cache.put(8, 2, 2, &pixels(8, 16));
+        }
+        let cache = DiskCache::open(&path).unwrap();
+        assert_eq!(cache.get(7, 2, 2), Some(pixels(7, 16)));
+        assert_eq!(cache.get(8, 2, 2), Some(pixels(8, 16)));
+        cache.put(9, 2, 2, &pixels(9, 16));
+        drop(cache);
+        let cache = DiskCache::open(&path).unwrap();
+        assert_eq!(cache.get(9, 2, 2), Some(pixels(9, 16)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn truncated_last_record_is_dropped() {
+        let path = temp_path("truncated");
+        {
+            let cache = DiskCache::open(&path).unwrap();
+            cache.put(1, 2, 2, &pixels(1, 16));
+            cache.put(2, 2, 2, &pixels(2, 16));
+        }
+        let complete = HEADER_LEN + RECORD_HEADER_LEN + 16;
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(complete + RECORD_HEADER_LEN + 5).unwrap();
+        drop(file);
+
+        let cache = DiskCache::open(&path).unwrap();
+        assert_eq!(cache.get(1, 2, 2), Some(pixels(1, 16)));
+        assert_eq!(cache.get(2, 2, 2), None);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), complete);
+        cache.put(2, 2, 2, &pixels(2, 16));
+        drop(cache);
+        let cache = DiskCache::open(&path).unwrap();
+        assert_eq!(cache.get(2, 2, 2), Some(pixels(2, 16)));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bad_header_resets() {
+        for (name, contents) in [
+            ("magic", b"PNG\x89\x01garbage".to_vec()),
+            ("version", [&MAGIC[..], &[VERSION + 1], &[0; 20]].concat()),
+            ("short", b"DX".to_vec()),
+        ] {
+            let path = temp_path(&format!("bad-header-{}", name));
+            std::fs::write(&path, contents).unwrap();
+            let cache = DiskCache::open(&path).unwrap();
+            assert_eq!(std::fs::metadata(&path).unwrap().len(), HEADER_LEN);
+            cache.put(1, 2, 2, &pixels(1, 16));
+            assert_eq!(cache.get(1, 2, 2), Some(pixels(1, 16)));
+            drop(cache);
+            let cache = DiskCache::open(&path).unwrap();
+            assert_eq!(cache.get(1, 2, 2), Some(pixels(1, 16)));
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn instances_sharing_a_file() {
+        let path = temp_path("shared");
+        let a = DiskCache::open(&path).unwrap();
+        let b = DiskCache::open(&path).unwrap();
+        a.put(1, 2, 2, &pixels(1, 16));
+        b.put(2, 2, 2, &pixels(2, 16));
+        a.put(3, 2, 2, &pixels(3, 16));
+        assert_eq!(a.get(1, 2, 2), Some(pixels(1, 16)));
+        assert_eq!(b.get(2, 2, 2), Some(pixels(2, 16)));
+        assert_eq!(a.get(3, 2, 2), Some(pixels(3, 16)));
+        drop((a, b));
+        let cache = DiskCache::open(&path).unwrap();
+        for id in 1..=3 {
+            assert_eq!(cache.get(id, 2, 2), Some(pixels(id as u8, 16)));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn stale_offsets_are_rejected() {
+        let path = temp_path("stale");
+        let a = DiskCache::open(&path).unwrap();
+        a.put(1, 2, 2, &pixels(1, 16));
+        // Another instance resets the file and writes a different sprite of
+        // the same size where #1 was.
+        std::fs::write(&path, b"junk").unwrap();
+        let b = DiskCache::open(&path).unwrap();
+        b.put(2, 2, 2, &pixels(2, 16));
+        assert_eq!(a.get(1, 2, 2), None);
+        assert_eq!(b.get(2, 2, 2), Some(pixels(2, 16)));
+        let _ = std::fs::remove_file(&path);
+    }
+}
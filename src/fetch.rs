@@ -1,9 +1,24 @@
-use crate::models::Pokemon;
-use reqwest;
-use serde_json;
+use crate::models::{DexNumber, Encounter, Pokemon};
+use crate::sprites::{animated_path, animated_url, SpriteVariant};
 use std::error::Error;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
+/// What `fetch_and_cache` should download.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// Number of Pokémon to fetch, starting from #1.
+    pub limit: usize,
+    /// Also download shiny, back, female and official-artwork sprites.
+    pub sprite_variants: bool,
+    /// Also download the sprite set of this generation (1..=8) from
+    /// `sprites.versions`.
+    pub sprite_generation: Option<u8>,
+    /// Also download animated front sprites (GIF), plus the shiny animation
+    /// when `sprite_variants` is set.
+    pub animated: bool,
+}
+
 #[derive(Debug)]
 pub struct FetchState {
     pub in_progress: bool,
@@ -11,10 +26,202 @@ pub struct FetchState {
     pub total: usize,
 }
 
+/// Flatten a `/pokemon/{id}/encounters` response into one entry per
+/// (location area, version, method), merging level ranges and chances.
+fn parse_encounters(e_json: &serde_json::Value) -> Vec<Encounter> {
+    let mut out: Vec<Encounter> = Vec::new();
+    let areas = match e_json.as_array() {
+        Some(a) => a,
+        None => return out,
+    };
+    for area in areas {
+        let location_area = area
+            .get("location_area")
+            .and_then(|l| l.get("name"))
+            .and_then(|n| n.as_str())
+            .unwrap_or_default();
+        let versions = area
+            .get("version_details")
+            .and_then(|v| v.as_array())
+            .map(|v| v.as_slice())
+            .unwrap_or_default();
+        for vd in versions {
+            let version = vd
+                .get("version")
+                .and_then(|v| v.get("name"))
+                .and_then(|n| n.as_str())
+                .unwrap_or_default();
+            let details = vd
+                .get("encounter_details")
+                .and_then(|d| d.as_array())
+                .map(|d| d.as_slice())
+                .unwrap_or_default();
+            for det in details {
+                let method = det
+                    .get("method")
+                    .and_then(|m| m.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default();
+                let min_level = det.get("min_level").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                let max_level = det.get("max_level").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                let chance = det.get("chance").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                if let Some(e) = out.iter_mut().find(|e| {
+                    e.location_area == location_area && e.version == version && e.method == method
+                }) {
+                    e.min_level = e.min_level.min(min_level);
+                    e.max_level = e.max_level.max(max_level);
+                    e.chance = (e.chance + chance).min(100);
+                } else {
+                    out.push(Encounter {
+                        location_area: location_area.to_string(),
+                        version: version.to_string(),
+                        method: method.to_string(),
+                        min_level,
+                        max_level,
+                        chance: chance.min(100),
+                    });
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Regional dex entries from a `/pokemon-species/{name}` response.
+fn parse_dex_numbers(s_json: &serde_json::Value) -> Vec<DexNumber> {
+    s_json
+        .get("pokedex_numbers")
+        .and_then(|p| p.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|it| {
+                    let dex = it
+                        .get("pokedex")
+                        .and_then(|d| d.get("name"))
+                        .and_then(|n| n.as_str())?;
+                    let number = it.get("entry_number").and_then(|n| n.as_u64())? as u32;
+                    Some(DexNumber {
+                        dex: dex.to_string(),
+                        number,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generation number (1-based) of a `/pokemon-species/{name}` response, from
+/// its `generation.name` (e.g. `generation-iii`).
+fn parse_species_generation(s_json: &serde_json::Value) -> Option<u8> {
+    const NUMERALS: [&str; 9] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+    let name = s_json
+        .get("generation")
+        .and_then(|g| g.get("name"))
+        .and_then(|n| n.as_str())?;
+    let numeral = name.strip_prefix("generation-")?;
+    NUMERALS
+        .iter()
+        .position(|n| *n == numeral)
+        .map(|i| i as u8 + 1)
+}
+
+fn parse_flag(s_json: &serde_json::Value, key: &str) -> bool {
+    s_json.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+async fn fetch_json(client: &reqwest::Client, url: &str) -> Option<serde_json::Value> {
+    let resp = client.get(url).send().await.ok()?;
+    resp.json::<serde_json::Value>().await.ok()
+}
+
+/// Species URL of a `/pokemon/{name}` response. Forms such as
+/// `deoxys-normal` or `giratina-altered` have no species of their own name.
+fn species_url(p_json: &serde_json::Value) -> Option<&str> {
+    p_json
+        .get("species")
+        .and_then(|s| s.get("url"))
+        .and_then(|u| u.as_str())
+}
+
+async fn fetch_species(
+    client: &reqwest::Client,
+    p_json: &serde_json::Value,
+) -> Option<serde_json::Value> {
+    fetch_json(client, species_url(p_json)?).await
+}
+
+/// Download `url` to `path`, creating parent directories. Returns whether the
+/// file was written.
+async fn download(client: &reqwest::Client, url: &str, path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    let bytes = match client.get(url).send().await {
+        Ok(resp) => match resp.bytes().await {
+            Ok(b) => b,
+            Err(_) => return false,
+        },
+        Err(_) => return false,
+    };
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    std::fs::write(path, &bytes).is_ok()
+}
+
+/// Download the animated front sprite (and its shiny version when `shiny`) and
+/// return the keys of the animations that were saved.
+async fn download_animated(
+    client: &reqwest::Client,
+    p_json: &serde_json::Value,
+    id: u32,
+    shiny: bool,
+) -> Vec<String> {
+    let mut saved = Vec::new();
+    for (s, key) in [(false, "front_default"), (true, "front_shiny")] {
+        if s && !shiny {
+            continue;
+        }
+        if let Some(url) = animated_url(p_json, s) {
+            if download(client, url, animated_path(id, s)).await {
+                saved.push(key.to_string());
+            }
+        }
+    }
+    saved
+}
+
+/// Download each of `variants` that PokeAPI lists for this Pokémon and return
+/// the keys of the variants that were saved.
+async fn download_sprites(
+    client: &reqwest::Client,
+    p_json: &serde_json::Value,
+    id: u32,
+    variants: Vec<SpriteVariant>,
+) -> Vec<String> {
+    let mut saved = Vec::new();
+    for variant in variants {
+        if let Some(url) = variant.url(p_json) {
+            if download(client, url, variant.path(id)).await {
+                saved.push(variant.key());
+            }
+        }
+    }
+    saved
+}
+
+/// Fetch wild encounter locations for Pokémon `id`. Returns `None` on network
+/// or decode errors so the next refresh tries again.
+async fn fetch_encounters(client: &reqwest::Client, id: u32) -> Option<Vec<Encounter>> {
+    let url = format!("https://pokeapi.co/api/v2/pokemon/{}/encounters", id);
+    let resp = client.get(&url).send().await.ok()?;
+    let e_json = resp.json::<serde_json::Value>().await.ok()?;
+    Some(parse_encounters(&e_json))
+}
+
 pub async fn fetch_and_cache(
-    limit: usize,
+    opts: FetchOptions,
     state: Option<Arc<Mutex<FetchState>>>,
 ) -> Result<Vec<Pokemon>, Box<dyn Error>> {
+    let limit = opts.limit;
     let cache_path = "data/pokemon.json";
     if let Ok(existing) = std::fs::read_to_string(cache_path) {
         if let Ok(mut v) = serde_json::from_str::<Vec<Pokemon>>(&existing) {
@@ -28,28 +235,40 @@ pub async fn fetch_and_cache(
                 let client = reqwest::Client::new();
                 for (i, p) in v.iter_mut().enumerate() {
                     let sprite_path = format!("data/sprites/{}.png", p.pokedex);
-                    if p.sprite.is_none()
+                    let need_details = p.sprite.is_none()
                         || !std::path::Path::new(&sprite_path).exists()
                         || p.abilities.is_empty()
                         || p.stats.is_empty()
                         || p.height == 0
                         || p.weight == 0
-                        || p.base_experience == 0
+                        || p.base_experience == 0;
+                    let need_variants = opts.sprite_variants && p.sprite_variants.is_none();
+                    // A generation fetched without variants is fetched again
+                    // once variants are wanted.
+                    let need_generation = opts.sprite_generation.filter(|g| {
+                        !p.sprite_generations.contains(g)
+                            || (opts.sprite_variants && !p.sprite_generation_variants.contains(g))
+                    });
+                    let need_animated = opts.animated && p.animated_sprites.is_none();
+                    let need_moves = p.moves.is_none();
+                    let need_species = p.dex_numbers.is_empty() || p.generation.is_none();
+                    if need_details
+                        || need_variants
+                        || need_generation.is_some()
+                        || need_animated
+                        || need_moves
+                        || need_species
                     {
                         let poke_url = format!("https://pokeapi.co/api/v2/pokemon/{}", p.name);
-                        if let Ok(p_res) = client.get(&poke_url).send().await {
-                            if let Ok(p_json) = p_res.json::<serde_json::Value>().await {
+                        if let Some(p_json) = fetch_json(&client, &poke_url).await {
+                            if need_details {
                                 if let Some(sprite_url) = p_json
                                     .get("sprites")
                                     .and_then(|s| s.get("front_default"))
                                     .and_then(|u| u.as_str())
                                 {
-                                    if let Ok(resp) = client.get(sprite_url).send().await {
-                                        if let Ok(bytes) = resp.bytes().await {
-                                            let _ = std::fs::create_dir_all("data/sprites");
-                                            let _ = std::fs::write(&sprite_path, &bytes);
-                                            p.sprite = Some(sprite_url.to_string());
-                                        }
+                                    if download(&client, sprite_url, &sprite_path).await {
+                                        p.sprite = Some(sprite_url.to_string());
                                     }
                                 }
 
@@ -66,7 +285,7 @@ pub async fn fetch_and_cache(
                                             })
                                             .collect()
                                     })
-                                    .unwrap_or_else(|| vec![]);
+                                    .unwrap_or_default();
                                 let height =
                                     p_json.get("height").and_then(|v| v.as_u64()).unwrap_or(0)
                                         as u32;
@@ -98,7 +317,7 @@ pub async fn fetch_and_cache(
                                             })
                                             .collect()
                                     })
-                                    .unwrap_or_else(|| vec![]);
+                                    .unwrap_or_default();
 
                                 p.abilities = abilities;
                                 p.height = height;
@@ -106,8 +325,69 @@ pub async fn fetch_and_cache(
                                 p.base_experience = base_experience;
                                 p.stats = stats;
                             }
+                            if need_variants {
+                                p.sprite_variants = Some(
+                                    download_sprites(
+                                        &client,
+                                        &p_json,
+                                        p.pokedex,
+                                        SpriteVariant::downloadable(),
+                                    )
+                                    .await,
+                                );
+                            }
+                            if let Some(g) = need_generation {
+                                let set =
+                                    SpriteVariant::generation_set(Some(g), opts.sprite_variants);
+                                download_sprites(&client, &p_json, p.pokedex, set).await;
+                                if !p.sprite_generations.contains(&g) {
+                                    p.sprite_generations.push(g);
+                                }
+                                if opts.sprite_variants {
+                                    p.sprite_generation_variants.push(g);
+                                }
+                            }
+                            if need_moves {
+                                let moves = p_json
+                                    .get("moves")
+                                    .and_then(|m| m.as_array())
+                                    .map(|arr| {
+                                        arr.iter()
+                                            .filter_map(|it| {
+                                                it.get("move")
+                                                    .and_then(|mv| mv.get("name"))
+                                                    .and_then(|n| n.as_str())
+                                                    .map(|s| s.to_string())
+                                            })
+                                            .collect()
+                                    })
+                                    .unwrap_or_default();
+                                p.moves = Some(moves);
+                            }
+                            if need_animated {
+                                p.animated_sprites = Some(
+                                    download_animated(
+                                        &client,
+                                        &p_json,
+                                        p.pokedex,
+                                        opts.sprite_variants,
+                                    )
+                                    .await,
+                                );
+                            }
+                            if need_species {
+                                if let Some(s_json) = fetch_species(&client, &p_json).await {
+                                    p.dex_numbers = parse_dex_numbers(&s_json);
+                                    p.generation = parse_species_generation(&s_json);
+                                    p.legendary = parse_flag(&s_json, "is_legendary");
+                                    p.mythical = parse_flag(&s_json, "is_mythical");
+                                }
+                            }
                         }
                     }
+                    if p.encounters.is_none() {
+                        p.encounters = fetch_encounters(&client, p.pokedex).await;
+                    }
                     if let Some(s) = &state {
                         let mut st = s.lock().unwrap();
                         st.fetched = i + 1;
@@ -147,12 +427,13 @@ pub async fn fetch_and_cache(
     for entry in results.iter() {
         if let Some(name) = entry.get("name").and_then(|n| n.as_str()) {
             let poke_url = format!("https://pokeapi.co/api/v2/pokemon/{}", name);
-            let p_res = client.get(&poke_url).send().await;
-            if p_res.is_err() {
-                eprintln!("failed to fetch {}: {}", name, p_res.unwrap_err());
-                continue;
-            }
-            let p_json: serde_json::Value = p_res.unwrap().json().await?;
+            let p_json: serde_json::Value = match client.get(&poke_url).send().await {
+                Ok(resp) => resp.json().await?,
+                Err(e) => {
+                    eprintln!("failed to fetch {}: {}", name, e);
+                    continue;
+                }
+            };
             let id = p_json.get("id").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
             let sprite_url = p_json
                 .get("sprites")
@@ -172,7 +453,7 @@ pub async fn fetch_and_cache(
                         })
                         .collect()
                 })
-                .unwrap_or_else(|| vec![]);
+                .unwrap_or_default();
 
             let abilities = p_json
                 .get("abilities")
@@ -187,7 +468,7 @@ pub async fn fetch_and_cache(
                         })
                         .collect()
                 })
-                .unwrap_or_else(|| vec![]);
+                .unwrap_or_default();
             let height = p_json.get("height").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
             let weight = p_json.get("weight").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
             let base_experience = p_json
@@ -212,36 +493,69 @@ pub async fn fetch_and_cache(
                         })
                         .collect()
                 })
-                .unwrap_or_else(|| vec![]);
-
-            let species_url = format!("https://pokeapi.co/api/v2/pokemon-species/{}", name);
-            let s_res = client.get(&species_url).send().await;
-            let description = if let Ok(sresp) = s_res {
-                let s_json: serde_json::Value =
-                    sresp.json().await.unwrap_or(serde_json::Value::Null);
-                if let Some(entries) = s_json.get("flavor_text_entries").and_then(|e| e.as_array())
-                {
-                    let mut found = None;
-                    for ent in entries {
-                        if ent
-                            .get("language")
-                            .and_then(|l| l.get("name"))
-                            .and_then(|n| n.as_str())
-                            == Some("en")
-                        {
-                            if let Some(ft) = ent.get("flavor_text").and_then(|f| f.as_str()) {
-                                found = Some(ft.replace('\n', " ").replace('\u{c}', " "));
-                                break;
-                            }
+                .unwrap_or_default();
+            let moves = p_json
+                .get("moves")
+                .and_then(|m| m.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|it| {
+                            it.get("move")
+                                .and_then(|mv| mv.get("name"))
+                                .and_then(|n| n.as_str())
+                                .map(|s| s.to_string())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let s_json = fetch_species(&client, &p_json)
+                .await
+                .unwrap_or(serde_json::Value::Null);
+            let description = if let Some(entries) =
+                s_json.get("flavor_text_entries").and_then(|e| e.as_array())
+            {
+                let mut found = None;
+                for ent in entries {
+                    if ent
+                        .get("language")
+                        .and_then(|l| l.get("name"))
+                        .and_then(|n| n.as_str())
+                        == Some("en")
+                    {
+                        if let Some(ft) = ent.get("flavor_text").and_then(|f| f.as_str()) {
+                            found = Some(ft.replace(['\n', '\u{c}'], " "));
+                            break;
                         }
                     }
-                    found.unwrap_or_else(|| "No description available.".to_string())
-                } else {
-                    "No description available.".to_string()
                 }
+                found.unwrap_or_else(|| "No description available.".to_string())
             } else {
                 "No description available.".to_string()
             };
+            let dex_numbers = parse_dex_numbers(&s_json);
+
+            let encounters = fetch_encounters(&client, id).await;
+            let sprite_variants = if opts.sprite_variants {
+                Some(download_sprites(&client, &p_json, id, SpriteVariant::downloadable()).await)
+            } else {
+                None
+            };
+            let mut sprite_generations = Vec::new();
+            let mut sprite_generation_variants = Vec::new();
+            if let Some(g) = opts.sprite_generation {
+                let set = SpriteVariant::generation_set(Some(g), opts.sprite_variants);
+                download_sprites(&client, &p_json, id, set).await;
+                sprite_generations.push(g);
+                if opts.sprite_variants {
+                    sprite_generation_variants.push(g);
+                }
+            }
+            let animated_sprites = if opts.animated {
+                Some(download_animated(&client, &p_json, id, opts.sprite_variants).await)
+            } else {
+                None
+            };
 
             pokemons.push(Pokemon {
                 name: name.to_string(),
@@ -254,15 +568,20 @@ pub async fn fetch_and_cache(
                 weight,
                 base_experience,
                 stats,
+                encounters,
+                dex_numbers,
+                sprite_variants,
+                sprite_generations,
+                sprite_generation_variants,
+                animated_sprites,
+                generation: parse_species_generation(&s_json),
+                legendary: parse_flag(&s_json, "is_legendary"),
+                mythical: parse_flag(&s_json, "is_mythical"),
+                moves: Some(moves),
             });
 
             if let Some(url) = sprite_url {
-                if let Ok(resp) = client.get(&url).send().await {
-                    if let Ok(bytes) = resp.bytes().await {
-                        let _ = std::fs::create_dir_all("data/sprites");
-                        let _ = std::fs::write(format!("data/sprites/{}.png", id), &bytes);
-                    }
-                }
+                download(&client, &url, format!("data/sprites/{}.png", id)).await;
             }
             if let Some(s) = &state {
                 let mut st = s.lock().unwrap();
@@ -285,3 +604,155 @@ pub async fn fetch_and_cache(
 
     Ok(pokemons)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn slot(method: &str, min: u64, max: u64, chance: u64) -> serde_json::Value {
+        json!({
+            "method": { "name": method },
+            "min_level": min,
+            "max_level": max,
+            "chance": chance,
+        })
+    }
+
+    #[test]
+    fn encounters_merge_slots() {
+        let e_json = json!([
+            {
+                "location_area": { "name": "viridian-forest-area" },
+                "version_details": [
+                    {
+                        "version": { "name": "red" },
+                        "encounter_details": [
+                            slot("walk", 3, 3, 5),
+                            slot("walk", 5, 5, 1),
+                            slot("surf", 20, 25, 10),
+                        ],
+                    },
+                    {
+                        "version": { "name": "blue" },
+                        "encounter_details": [slot("walk", 4, 6, 5)],
+                    },
+                ],
+            },
+            {
+                "location_area": { "name": "power-plant-area" },
+                "version_details": [{
+                    "version": { "name": "red" },
+                    "encounter_details": [slot("walk", 22, 22, 60), slot("walk", 24, 26, 70)],
+                }],
+            },
+        ]);
+        let found: Vec<_> = parse_encounters(&e_json)
+            .into_iter()
+            .map(|e| {
+                (
+                    e.location_area,
+                    e.version,
+                    e.method,
+                    e.min_level,
+                    e.max_level,
+                    e.chance,
+                )
+            })
+            .collect();
+        let entry = |area: &str, version: &str, method: &str, min, max, chance| {
+            (
+                area.to_string(),
+                version.to_string(),
+                method.to_string(),
+                min,
+                max,
+                chance,
+            )
+        };
+        assert_eq!(
+            found,
+            vec![
+                entry("viridian-forest-area", "red", "walk", 3, 5, 6),
+                entry("viridian-forest-area", "red", "surf", 20, 25, 10),
+                entry("viridian-forest-area", "blue", "walk", 4, 6, 5),
+                entry("power-plant-area", "red", "walk", 22, 26, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn encounters_of_unexpected_json() {
+        assert!(parse_encounters(&json!({})).is_empty());
+        assert!(parse_encounters(&json!([])).is_empty());
+        let e_json = json!([{ "location_area": { "name": "route-1-area" } }]);
+        assert!(parse_encounters(&e_json).is_empty());
+        let e_json = json!([{
+            "version_details": [{ "encounter_details": [{ "chance": 250 }] }],
+        }]);
+        let found = parse_encounters(&e_json);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].location_area, "");
+        assert_eq!(found[0].chance, 100);
+    }
+
+    #[test]
+    fn dex_numbers() {
+        let s_json = json!({
+            "pokedex_numbers": [
+                { "entry_number": 25, "pokedex": { "name": "national" } },
+                { "entry_number": 22, "pokedex": { "name": "original-johto" } },
+                { "entry_number": 104, "pokedex": {} },
+                { "pokedex": { "name": "kalos-central" } },
+            ],
+        });
+        let found: Vec<_> = parse_dex_numbers(&s_json)
+            .into_iter()
+            .map(|d| (d.dex, d.number))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("national".to_string(), 25),
+                ("original-johto".to_string(), 22),
+            ]
+        );
+        assert!(parse_dex_numbers(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn species_generation() {
+        let generation =
+            |name: &str| parse_species_generation(&json!({ "generation": { "name": name } }));
+        assert_eq!(generation("generation-i"), Some(1));
+        assert_eq!(generation("generation-iv"), Some(4));
+        assert_eq!(generation("generation-ix"), Some(9));
+        assert_eq!(generation("generation-x"), None);
+        assert_eq!(generation("iv"), None);
+        assert_eq!(parse_species_generation(&json!({})), None);
+    }
+
+    #[test]
+    fn species_of_a_form() {
+        let p_json = json!({
+            "name": "giratina-altered",
+            "species": {
+                "name": "giratina",
+                "url": "https://pokeapi.co/api/v2/pokemon-species/487/",
+            },
+        });
+        assert_eq!(
+            species_url(&p_json),
+            Some("https://pokeapi.co/api/v2/pokemon-species/487/")
+        );
+        assert_eq!(species_url(&json!({ "name": "giratina-altered" })), None);
+    }
+
+    #[test]
+    fn flags() {
+        let s_json = json!({ "is_legendary": true, "is_mythical": "yes" });
+        assert!(parse_flag(&s_json, "is_legendary"));
+        assert!(!parse_flag(&s_json, "is_mythical"));
+        assert!(!parse_flag(&s_json, "is_baby"));
+    }
+}
@@ -0,0 +1,157 @@
+//! Fuzzy (subsequence) matching for the search box. The typed letters must
+//! appear in the name in order but may skip letters, so omissions ("charzard",
+//! "pkchu") still find the Pokémon; extra or wrong letters ("charizzard",
+//! "pikachoo") do not.
+
+/// Score of a matched pattern character.
+const MATCH: i32 = 16;
+/// Extra score when the previous pattern character matched right before.
+const CONSECUTIVE: i32 = 8;
+/// Extra score for matching the first character of a word.
+const WORD_START: i32 = 10;
+/// Penalty for starting a gap between matched characters, and for each
+/// further skipped character.
+const GAP_START: i32 = 3;
+const GAP_EXTEND: i32 = 1;
+
+/// A fuzzy match: its score (higher is better) and the char indices of `text`
+/// that matched.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    pub positions: Vec<usize>,
+}
+
+/// Match `pattern` as a case-insensitive subsequence of `text`. The match uses
+/// the shortest window ending at the first place the whole pattern fits, which
+/// keeps the matched characters together.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    // Forward: end of the first window containing the pattern.
+    let mut pi = 0;
+    let mut end = None;
+    for (ti, c) in text.iter().enumerate() {
+        if *c == pattern[pi] {
+            pi += 1;
+            if pi == pattern.len() {
+                end = Some(ti);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward: the latest start that still fits, giving the tightest window.
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut pi = pattern.len();
+    for ti in (0..=end).rev() {
+        if text[ti] == pattern[pi - 1] {
+            positions.push(ti);
+            pi -= 1;
+            if pi == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    let mut score = 0;
+    let mut prev: Option<usize> = None;
+    for &pos in &positions {
+        score += MATCH;
+        if pos == 0 || matches!(text[pos - 1], ' ' | '-' | '_' | '.') {
+            score += WORD_START;
+        }
+        match prev {
+            Some(p) if pos == p + 1 => score += CONSECUTIVE,
+            Some(p) => score -= GAP_START + GAP_EXTEND * (pos - p - 2) as i32,
+            None => {}
+        }
+        prev = Some(pos);
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> i32 {
+        fuzzy_match(pattern, text).unwrap().score
+    }
+
+    fn positions(pattern: &str, text: &str) -> Vec<usize> {
+        fuzzy_match(pattern, text).unwrap().positions
+    }
+
+    #[test]
+    fn omissions_match_but_additions_do_not() {
+        assert!(fuzzy_match("charzard", "Charizard").is_some());
+        assert!(fuzzy_match("pkchu", "Pikachu").is_some());
+        assert!(fuzzy_match("PIKA", "Pikachu").is_some());
+        assert_eq!(fuzzy_match("charizzard", "Charizard"), None);
+        assert_eq!(fuzzy_match("pikachoo", "Pikachu"), None);
+        assert_eq!(fuzzy_match("ab", "Bulba"), None);
+    }
+
+    #[test]
+    fn empty_pattern_matches_with_no_score() {
+        assert_eq!(
+            fuzzy_match("", "Mew"),
+            Some(FuzzyMatch {
+                score: 0,
+                positions: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn scores() {
+        // Prefix: four matches, a word start and three consecutive letters.
+        assert_eq!(
+            score("pika", "Pikachu"),
+            4 * MATCH + WORD_START + 3 * CONSECUTIVE
+        );
+        // One skipped letter opens a gap.
+        assert_eq!(
+            score("charzard", "Charizard"),
+            8 * MATCH + WORD_START + 6 * CONSECUTIVE - GAP_START
+        );
+        // Each further skipped letter extends it.
+        assert_eq!(
+            score("mt", "Mewtwo"),
+            2 * MATCH + WORD_START - GAP_START - GAP_EXTEND
+        );
+    }
+
+    #[test]
+    fn positions_use_the_tightest_window() {
+        assert_eq!(positions("pika", "Pikachu"), vec![0, 1, 2, 3]);
+        assert_eq!(positions("ba", "Bulbasaur"), vec![3, 4]);
+        assert_eq!(positions("mt", "Mewtwo"), vec![0, 3]);
+        assert_eq!(positions("char", "Chimchar"), vec![4, 5, 6, 7]);
+        // Char indices, not byte indices.
+        assert_eq!(positions("ébé", "Flabébé"), vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn ranking() {
+        let mut names = vec!["Chimchar", "Charmander", "Mr. Mime", "Mamoswine"];
+        names.retain(|n| fuzzy_match("char", n).is_some());
+        names.sort_by_key(|n| -score("char", n));
+        assert_eq!(names, vec!["Charmander", "Chimchar"]);
+
+        // A second word start outweighs a shorter gap.
+        assert!(score("mm", "Mr. Mime") > score("mm", "Mamoswine"));
+        // Consecutive letters beat scattered ones.
+        assert!(score("saur", "Bulbasaur") > score("saur", "Sandslash Pursuer"));
+    }
+}
@@ -0,0 +1,398 @@
+//! Kitty graphics protocol and Sixel output for true-resolution sprites.
+//!
+//! These bypass ratatui's cell buffer: `draw_ui` leaves the sprite area blank and
+//! records a `GraphicRequest`, and the escape sequence is written straight to the
+//! terminal after the frame has been flushed.
+
+use base64::Engine;
+use crossterm::cursor::MoveTo;
+use crossterm::QueueableCommand;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use std::fmt::Write as _;
+use std::io;
+use std::path::PathBuf;
+
+use crate::sprites::{crop_to_content, ALPHA_THRESHOLD};
+
+/// Kitty image id used for the sprite panel, so the previous sprite can be
+/// deleted without touching other images on screen.
+const KITTY_IMAGE_ID: u32 = 7031;
+
+/// Kitty limits each escape sequence payload to 4096 bytes of base64.
+const KITTY_CHUNK: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// Work out which protocol the terminal supports. `POKEMON_GRAPHICS`
+    /// (`kitty`, `sixel` or `none`) overrides detection; otherwise the terminal
+    /// is identified from `TERM`, `TERM_PROGRAM` and terminal-specific variables.
+    /// The terminal itself is never queried, so one that is not recognized here
+    /// (or kitty behind ssh with another `TERM`) needs the override or
+    /// `--graphics`.
+    pub fn detect() -> Option<Self> {
+        let var = |k: &str| std::env::var(k).unwrap_or_default().to_lowercase();
+        if let Ok(forced) = std::env::var("POKEMON_GRAPHICS") {
+            return Self::parse(&forced);
+        }
+        // tmux and screen swallow graphics escapes unless specially configured.
+        if !var("TMUX").is_empty() || var("TERM").starts_with("screen") {
+            return None;
+        }
+        let term = var("TERM");
+        let program = var("TERM_PROGRAM");
+        if term.contains("kitty")
+            || !var("KITTY_WINDOW_ID").is_empty()
+            || term.contains("ghostty")
+            || program == "wezterm"
+            || program == "ghostty"
+        {
+            return Some(GraphicsProtocol::Kitty);
+        }
+        if term.contains("sixel")
+            || term.starts_with("foot")
+            || term.starts_with("mlterm")
+            || program == "mintty"
+            || program == "iterm.app"
+        {
+            return Some(GraphicsProtocol::Sixel);
+        }
+        None
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "kitty" => Some(GraphicsProtocol::Kitty),
+            "sixel" => Some(GraphicsProtocol::Sixel),
+            _ => None,
+        }
+    }
+}
+
+/// A sprite to draw with a graphics protocol once the frame has been flushed.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GraphicRequest {
+    pub protocol: GraphicsProtocol,
+    pub path: PathBuf,
+    /// Cell area to cover.
+    pub area: Rect,
+}
+
+/// Decode the sprite of `req` and encode it as an escape sequence for its
+/// protocol. This is the slow part of showing a graphic, so it runs on the
+/// sprite loader thread.
+pub fn encode(req: &GraphicRequest) -> Option<String> {
+    let img = crop_to_content(&image::open(&req.path).ok()?.to_rgba8());
+    Some(match req.protocol {
+        GraphicsProtocol::Kitty => kitty_sequence(&img, req.area.width, req.area.height),
+        GraphicsProtocol::Sixel => {
+            sixel_sequence(&sixel_fit(&img, req.area.width, req.area.height))
+        }
+    })
+}
+
+/// Replace the graphic currently on screen (`shown`) with `wanted`, whose
+/// encoded sequence is `seq`. Nothing is written when they are equal, so an
+/// unchanged sprite is not re-sent every tick. While `seq` is still being
+/// encoded the old graphic is removed and nothing is drawn.
+///
+/// Kitty images are deleted by id. A Sixel image is just pixels on screen, so
+/// its cells are repainted from `frame` (the frame just drawn): ratatui only
+/// writes cells that changed, and would leave the image in place otherwise.
+pub fn sync(
+    out: &mut impl io::Write,
+    shown: &Option<GraphicRequest>,
+    wanted: &Option<GraphicRequest>,
+    seq: Option<&str>,
+    frame: &Buffer,
+) -> io::Result<()> {
+    if shown == wanted {
+        return Ok(());
+    }
+    match shown {
+        Some(GraphicRequest {
+            protocol: GraphicsProtocol::Kitty,
+            ..
+        }) => out.write_all(kitty_delete().as_bytes())?,
+        Some(GraphicRequest {
+            protocol: GraphicsProtocol::Sixel,
+            area,
+            ..
+        }) => {
+            let area = area.intersection(frame.area);
+            let cells = (area.top()..area.bottom())
+                .flat_map(|y| (area.left()..area.right()).map(move |x| (x, y, frame.get(x, y))));
+            CrosstermBackend::new(&mut *out).draw(cells)?;
+        }
+        None => {}
+    }
+    if let (Some(req), Some(seq)) = (wanted, seq) {
+        out.queue(MoveTo(req.area.x, req.area.y))?;
+        out.write_all(seq.as_bytes())?;
+    }
+    out.flush()
+}
+
+/// Size of one terminal cell in pixels, used to size Sixel images. Kitty scales
+/// images to a cell area itself so it does not need this. Configured with
+/// `POKEMON_CELL_SIZE=WxH`; defaults to 10x20.
+pub fn cell_size() -> (u32, u32) {
+    std::env::var("POKEMON_CELL_SIZE")
+        .ok()
+        .and_then(|s| {
+            let (w, h) = s.split_once('x')?;
+            Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+        })
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .unwrap_or((10, 20))
+}
+
+/// Transmit and display `img` scaled to `cols` x `rows` cells at the cursor.
+/// The payload is raw RGBA (`f=32`) split into chunks with `m=1` on all but the
+/// last; `q=2` suppresses responses and `C=1` keeps the cursor in place.
+pub fn kitty_sequence(img: &RgbaImage, cols: u16, rows: u16) -> String {
+    let payload = base64::engine::general_purpose::STANDARD.encode(img.as_raw());
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut out = String::with_capacity(payload.len() + chunks.len() * 16 + 64);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=32,s={},v={},c={},r={},i={},q=2,C=1,m={};",
+                img.width(),
+                img.height(),
+                cols,
+                rows,
+                KITTY_IMAGE_ID,
+                more
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={};", more);
+        }
+        // base64 output is ASCII, so the chunk is valid UTF-8.
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\x1b\\");
+    }
+    out
+}
+
+/// Remove the sprite image placed by `kitty_sequence` (and free its data).
+pub fn kitty_delete() -> String {
+    format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)
+}
+
+/// Index of `(r, g, b)` in the 6x6x6 color cube used as the Sixel palette.
+fn cube_index(r: u8, g: u8, b: u8) -> usize {
+    let q = |c: u8| (c as usize * 5 + 127) / 255;
+    q(r) * 36 + q(g) * 6 + q(b)
+}
+
+/// Encode `img` as a Sixel image. Colors are quantized to a 216-color cube and
+/// pixels below the alpha threshold are left unpainted (`P2=1` makes them
+/// transparent), so the terminal background shows through.
+pub fn sixel_sequence(img: &RgbaImage) -> String {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let mut out = String::new();
+    let _ = write!(out, "\x1bP0;1;0q\"1;1;{};{}", w, h);
+
+    let index: Vec<Option<usize>> = img
+        .pixels()
+        .map(|p| (p[3] >= ALPHA_THRESHOLD).then(|| cube_index(p[0], p[1], p[2])))
+        .collect();
+    let mut used = [false; 216];
+    for c in index.iter().flatten() {
+        used[*c] = true;
+    }
+    for (c, _) in used.iter().enumerate().filter(|(_, u)| **u) {
+        let level = |v: usize| v * 100 / 5;
+        let _ = write!(
+            out,
+            "#{};2;{};{};{}",
+            c,
+            level(c / 36),
+            level(c / 6 % 6),
+            level(c % 6)
+        );
+    }
+
+    for band in 0..h.div_ceil(6) {
+        let y0 = band * 6;
+        let mut first = true;
+        for color in (0..216).filter(|c| used[*c]) {
+            // Bit `dy` of each sixel is set when pixel (x, y0 + dy) has `color`.
+            let sixels: Vec<u8> = (0..w)
+                .map(|x| {
+                    (0..6)
+                        .filter(|dy| {
+                            let y = y0 + dy;
+                            y < h && index[y * w + x] == Some(color)
+                        })
+                        .fold(0u8, |bits, dy| bits | (1 << dy))
+                })
+                .collect();
+            if sixels.iter().all(|s| *s == 0) {
+                continue;
+            }
+            if !first {
+                out.push('$');
+            }
+            first = false;
+            let _ = write!(out, "#{}", color);
+            push_sixel_runs(&mut out, &sixels);
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Append sixel characters with run-length encoding (`!<n><char>`) for runs
+/// longer than three.
+fn push_sixel_runs(out: &mut String, sixels: &[u8]) {
+    let mut i = 0;
+    while i < sixels.len() {
+        let ch = (sixels[i] + 63) as char;
+        let run = sixels[i..].iter().take_while(|s| **s == sixels[i]).count();
+        if run > 3 {
+            let _ = write!(out, "!{}{}", run, ch);
+        } else {
+            for _ in 0..run {
+                out.push(ch);
+            }
+        }
+        i += run;
+    }
+}
+
+/// Scale `img` for a Sixel image filling `cols` x `rows` cells. Nearest-neighbour
+/// keeps pixel-art sprites crisp.
+pub fn sixel_fit(img: &RgbaImage, cols: u16, rows: u16) -> RgbaImage {
+    let (cw, ch) = cell_size();
+    let side = (cols as u32 * cw).min(rows as u32 * ch).max(1);
+    image::imageops::resize(img, side, side, FilterType::Nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(w: u32, h: u32, px: [u8; 4]) -> RgbaImage {
+        RgbaImage::from_pixel(w, h, image::Rgba(px))
+    }
+
+    #[test]
+    fn kitty_single_chunk() {
+        let img = solid(2, 1, [255, 0, 0, 255]);
+        let seq = kitty_sequence(&img, 4, 2);
+        assert_eq!(
+            seq,
+            format!(
+                "\x1b_Ga=T,f=32,s=2,v=1,c=4,r=2,i={},q=2,C=1,m=0;/wAA//8AAP8=\x1b\\",
+                KITTY_IMAGE_ID
+            )
+        );
+    }
+
+    #[test]
+    fn kitty_splits_large_payloads() {
+        // 32x32 RGBA = 4096 bytes -> 5464 base64 chars -> two chunks.
+        let img = solid(32, 32, [1, 2, 3, 255]);
+        let seq = kitty_sequence(&img, 10, 5);
+        let parts: Vec<&str> = seq.split("\x1b\\").filter(|s| !s.is_empty()).collect();
+        assert_eq!(parts.len(), 2);
+        assert!(parts[0].starts_with("\x1b_Ga=T,f=32,s=32,v=32,c=10,r=5,"));
+        assert!(parts[0].contains(",m=1;"));
+        assert!(parts[1].starts_with("\x1b_Gm=0;"));
+        assert_eq!(parts[0].split_once(';').unwrap().1.len(), KITTY_CHUNK);
+    }
+
+    #[test]
+    fn kitty_delete_targets_sprite_image() {
+        assert_eq!(
+            kitty_delete(),
+            format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", KITTY_IMAGE_ID)
+        );
+    }
+
+    fn sixel_request(area: Rect) -> Option<GraphicRequest> {
+        Some(GraphicRequest {
+            protocol: GraphicsProtocol::Sixel,
+            path: PathBuf::from("sprite.png"),
+            area,
+        })
+    }
+
+    #[test]
+    fn sync_repaints_cells_under_removed_sixel() {
+        let mut frame = Buffer::empty(Rect::new(0, 0, 10, 5));
+        frame.get_mut(3, 2).set_symbol("x");
+        let mut out = Vec::new();
+        sync(
+            &mut out,
+            &sixel_request(Rect::new(2, 1, 3, 2)),
+            &None,
+            None,
+            &frame,
+        )
+        .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        // Both rows of the old image are rewritten from the frame, including
+        // the non-blank cell, and no new image is sent.
+        assert!(out.contains("\x1b[2;3H"));
+        assert!(out.contains("\x1b[3;3H"));
+        assert!(out.contains('x'));
+        assert_eq!(out.matches(' ').count(), 5);
+        assert!(!out.contains("\x1bP"));
+    }
+
+    #[test]
+    fn sync_leaves_unchanged_graphic_alone() {
+        let frame = Buffer::empty(Rect::new(0, 0, 10, 5));
+        let req = sixel_request(Rect::new(2, 1, 3, 2));
+        let mut out = Vec::new();
+        sync(&mut out, &req, &req, Some("seq"), &frame).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn sixel_single_color_band() {
+        // 5x6 white: one band, one color, all six bits set ('~') run-length encoded.
+        let img = solid(5, 6, [255, 255, 255, 255]);
+        assert_eq!(
+            sixel_sequence(&img),
+            "\x1bP0;1;0q\"1;1;5;6#215;2;100;100;100#215!5~-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_skips_transparent_pixels() {
+        // Top row opaque black, second row transparent: bit 0 only ('@').
+        let mut img = solid(2, 2, [0, 0, 0, 0]);
+        img.put_pixel(0, 0, image::Rgba([0, 0, 0, 255]));
+        img.put_pixel(1, 0, image::Rgba([0, 0, 0, 255]));
+        assert_eq!(
+            sixel_sequence(&img),
+            "\x1bP0;1;0q\"1;1;2;2#0;2;0;0;0#0@@-\x1b\\"
+        );
+    }
+
+    #[test]
+    fn sixel_separates_colors_within_band() {
+        let mut img = solid(2, 1, [255, 0, 0, 255]);
+        img.put_pixel(1, 0, image::Rgba([0, 0, 255, 255]));
+        let seq = sixel_sequence(&img);
+        assert_eq!(
+            seq,
+            "\x1bP0;1;0q\"1;1;2;1#5;2;0;0;100#180;2;100;0;0#5?@$#180@?-\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Search history, kept one query per line in `history` in the config
+//! directory (oldest first).
+
+use crate::utils::config_dir;
+use std::io;
+use std::path::PathBuf;
+
+const FILE_NAME: &str = "history";
+
+/// Oldest entries are dropped beyond this.
+const MAX_ENTRIES: usize = 200;
+
+fn path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join(FILE_NAME))
+}
+
+pub fn load() -> Vec<String> {
+    path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .map(|s| s.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub fn save(entries: &[String]) -> io::Result<()> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut text = entries.join("\n");
+    text.push('\n');
+    std::fs::write(path, text)
+}
+
+/// Add `query` as the newest entry, moving it there if it was already in the
+/// history. Blank queries are ignored.
+pub fn push(entries: &mut Vec<String>, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    entries.retain(|e| e != query);
+    entries.push(query.to_string());
+    if entries.len() > MAX_ENTRIES {
+        entries.drain(..entries.len() - MAX_ENTRIES);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> Vec<String> {
+        entries.iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn push_trims_and_skips_blanks() {
+        let mut entries = Vec::new();
+        push(&mut entries, "  type:fire  ");
+        push(&mut entries, "");
+        push(&mut entries, "   ");
+        assert_eq!(entries, history(&["type:fire"]));
+    }
+
+    #[test]
+    fn push_moves_repeats_to_newest() {
+        let mut entries = history(&["pika", "type:fire", "legendary"]);
+        push(&mut entries, "type:fire ");
+        assert_eq!(entries, history(&["pika", "legendary", "type:fire"]));
+        push(&mut entries, "type:fire");
+        assert_eq!(entries, history(&["pika", "legendary", "type:fire"]));
+    }
+
+    #[test]
+    fn push_keeps_the_newest_entries() {
+        let mut entries = Vec::new();
+        for i in 0..MAX_ENTRIES + 5 {
+            push(&mut entries, &format!("#{}", i));
+        }
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0], "#5");
+        assert_eq!(entries.last(), Some(&format!("#{}", MAX_ENTRIES + 4)));
+    }
+}
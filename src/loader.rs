@@ -0,0 +1,180 @@
+//! Background sprite decoding.
+//!
+//! Everything that reads and decodes an image file (thumbnails, animation
+//! frames, graphics protocol sequences) happens on one worker thread. The UI
+//! only looks results up in the shared caches; on a miss it asks the worker for
+//! the sprite and draws a placeholder until it is ready.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use crate::diskcache::DiskCache;
+use crate::graphics::{self, GraphicRequest};
+use crate::sprites::{
+    animated_path, load_animation, load_thumb, AnimationKey, FrameCache, ThumbCache, ThumbKey,
+};
+
+/// Encoded graphics protocol sequences kept for redrawing; a handful is enough
+/// for the selection and the entries just visited.
+const GRAPHIC_ENTRIES: usize = 16;
+
+/// Encoded graphics protocol sequences by request.
+pub type GraphicCache = HashMap<GraphicRequest, String>;
+
+/// Something the UI needs decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadRequest {
+    Thumb(ThumbKey),
+    /// Animated sprite, with frames sized for drawing.
+    Animation(AnimationKey),
+    Graphic(GraphicRequest),
+}
+
+enum Message {
+    /// Needed by the current frame; served before any prefetching.
+    Now(LoadRequest),
+    /// Thumbnails around the selection. Replaces any earlier prefetch that has
+    /// not been served yet, so scrolling quickly through the list does not
+    /// queue up work for entries that are no longer near the selection.
+    Prefetch(Vec<ThumbKey>),
+}
+
+/// Handle to the loader thread. The caches are shared with the UI, which holds
+/// their locks only for lookups; decoding happens with no lock held.
+pub struct SpriteLoader {
+    tx: Sender<Message>,
+    /// Thumbnails whose sprite could not be decoded, so the UI stops waiting.
+    failed: Arc<Mutex<HashSet<ThumbKey>>>,
+}
+
+impl SpriteLoader {
+    pub fn spawn(
+        thumbs: Arc<Mutex<ThumbCache>>,
+        frames: Arc<Mutex<FrameCache>>,
+        graphics: Arc<Mutex<GraphicCache>>,
+        disk: Option<Arc<DiskCache>>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let failed = Arc::new(Mutex::new(HashSet::new()));
+        let worker = Worker {
+            thumbs,
+            frames,
+            graphics,
+            disk,
+            failed: failed.clone(),
+        };
+        std::thread::spawn(move || worker.run(rx));
+        SpriteLoader { tx, failed }
+    }
+
+    /// Whether the thumbnail for `key` failed to decode.
+    pub fn failed(&self, key: &ThumbKey) -> bool {
+        self.failed.lock().unwrap().contains(key)
+    }
+
+    /// Forget failed thumbnails so they are tried again, e.g. after a refresh
+    /// downloaded new sprites.
+    pub fn reset_failed(&self) {
+        self.failed.lock().unwrap().clear();
+    }
+
+    /// Ask for `req` to be decoded ahead of any prefetching.
+    pub fn request(&self, req: LoadRequest) {
+        let _ = self.tx.send(Message::Now(req));
+    }
+
+    /// Replace the pending prefetch with `keys` (nearest first).
+    pub fn prefetch(&self, keys: Vec<ThumbKey>) {
+        let _ = self.tx.send(Message::Prefetch(keys));
+    }
+}
+
+struct Worker {
+    thumbs: Arc<Mutex<ThumbCache>>,
+    frames: Arc<Mutex<FrameCache>>,
+    graphics: Arc<Mutex<GraphicCache>>,
+    disk: Option<Arc<DiskCache>>,
+    failed: Arc<Mutex<HashSet<ThumbKey>>>,
+}
+
+impl Worker {
+    fn run(&self, rx: Receiver<Message>) {
+        let mut urgent: VecDeque<LoadRequest> = VecDeque::new();
+        let mut prefetch: VecDeque<ThumbKey> = VecDeque::new();
+        loop {
+            // Block only when there is nothing left to do, then pick up
+            // everything that arrived in the meantime.
+            if urgent.is_empty() && prefetch.is_empty() {
+                match rx.recv() {
+                    Ok(msg) => self.accept(msg, &mut urgent, &mut prefetch),
+                    Err(_) => return,
+                }
+            }
+            while let Ok(msg) = rx.try_recv() {
+                self.accept(msg, &mut urgent, &mut prefetch);
+            }
+            match urgent.pop_front() {
+                Some(req) => self.load(req),
+                None => {
+                    if let Some(key) = prefetch.pop_front() {
+                        self.load(LoadRequest::Thumb(key));
+                    }
+                }
+            }
+        }
+    }
+
+    fn accept(
+        &self,
+        msg: Message,
+        urgent: &mut VecDeque<LoadRequest>,
+        prefetch: &mut VecDeque<ThumbKey>,
+    ) {
+        match msg {
+            // The UI asks again on every redraw until the result shows up.
+            Message::Now(req) if !urgent.contains(&req) => urgent.push_back(req),
+            Message::Now(_) => {}
+            Message::Prefetch(keys) => *prefetch = keys.into(),
+        }
+    }
+
+    fn load(&self, req: LoadRequest) {
+        match req {
+            LoadRequest::Thumb(want) => {
+                if let Some((variant, path)) = want.variant.resolve(want.id) {
+                    let key = ThumbKey { variant, ..want };
+                    if self.thumbs.lock().unwrap().contains(&key) {
+                        return;
+                    }
+                    match load_thumb(&path, key.w, key.h, self.disk.as_deref()) {
+                        Some(thumb) => self.thumbs.lock().unwrap().insert(key, thumb),
+                        None => {
+                            self.failed.lock().unwrap().insert(key);
+                        }
+                    }
+                }
+            }
+            LoadRequest::Animation(key) => {
+                if self.frames.lock().unwrap().get(&key).is_some() {
+                    return;
+                }
+                let anim = load_animation(&animated_path(key.id, key.shiny), key.w, key.h);
+                self.frames.lock().unwrap().insert(key, anim);
+            }
+            LoadRequest::Graphic(req) => {
+                if self.graphics.lock().unwrap().contains_key(&req) {
+                    return;
+                }
+                // A sprite that fails to decode is stored as an empty sequence
+                // so the UI stops asking for it.
+                let seq = graphics::encode(&req).unwrap_or_default();
+                let mut cache = self.graphics.lock().unwrap();
+                if cache.len() >= GRAPHIC_ENTRIES {
+                    cache.clear();
+                }
+                cache.insert(req, seq);
+            }
+        }
+    }
+}
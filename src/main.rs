@@ -1,9 +1,23 @@
+mod calc;
+mod diskcache;
 mod fetch;
+mod fuzzy;
+mod graphics;
+mod history;
+mod loader;
 mod models;
+mod presets;
+mod query;
+mod radar;
+mod render;
+mod sprites;
+mod stats;
+mod textindex;
+mod types;
 mod ui;
 mod utils;
 
-use crossterm::event::{self, Event as CEvent, KeyCode};
+use crossterm::event::{self, Event as CEvent, KeyCode, KeyModifiers};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
@@ -15,8 +29,12 @@ use std::io;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crate::fetch::{fetch_and_cache, FetchState};
-use crate::ui::{draw_ui, App, SpriteThumb};
+use crate::diskcache::{DiskCache, DISK_CACHE_PATH};
+use crate::fetch::{fetch_and_cache, FetchOptions, FetchState};
+use crate::graphics::GraphicsProtocol;
+use crate::render::{ColorDepth, Palette, RenderMode, SpriteBackground};
+use crate::sprites::parse_generation;
+use crate::ui::{draw_ui, App, DEFAULT_CACHE_BUDGET};
 use crate::utils::load_data;
 
 #[tokio::main]
@@ -31,9 +49,32 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Support a CLI argument to only fetch and exit (useful for seeding data).
     let args: Vec<String> = std::env::args().collect();
     let fetch_only = args.iter().any(|a| a == "--fetch-only");
+
+    // Optional sprite variants (shiny, back, female, official artwork) are only
+    // downloaded on request: `--sprite-variants` or `POKEMON_SPRITE_VARIANTS=1`.
+    let sprite_variants = args.iter().any(|a| a == "--sprite-variants")
+        || std::env::var("POKEMON_SPRITE_VARIANTS").is_ok_and(|v| v == "1");
+    // Generation-scoped sprite set to download and display (e.g. `1` for the
+    // Red/Blue look): `--sprite-gen=N` or `POKEMON_SPRITE_GEN=N`.
+    let sprite_generation = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--sprite-gen="))
+        .map(str::to_string)
+        .or_else(|| std::env::var("POKEMON_SPRITE_GEN").ok())
+        .and_then(|g| parse_generation(&g));
+    // Animated sprites (Gen V / Showdown GIFs): `--animated` or `POKEMON_ANIMATED=1`.
+    let animated = args.iter().any(|a| a == "--animated")
+        || std::env::var("POKEMON_ANIMATED").is_ok_and(|v| v == "1");
+    let fetch_opts = FetchOptions {
+        limit: fetch_limit,
+        sprite_variants,
+        sprite_generation,
+        animated,
+    };
+
     if fetch_only {
         eprintln!("Running fetch-only mode (limit={})...", fetch_limit);
-        let pokes = fetch_and_cache(fetch_limit, None).await?;
+        let pokes = fetch_and_cache(fetch_opts, None).await?;
         eprintln!(
             "Fetch complete: {} pokémon saved to data/pokemon.json",
             pokes.len()
@@ -51,9 +92,9 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Shared slot where background fetch writes updated data for the main loop to pick up
     let updated_data: Arc<Mutex<Option<Vec<models::Pokemon>>>> = Arc::new(Mutex::new(None));
     let updated_data_clone = updated_data.clone();
-    let fl_clone = fetch_limit;
+    let opts_clone = fetch_opts.clone();
     tokio::spawn(async move {
-        if let Ok(p) = fetch_and_cache(fl_clone, Some(fetch_state_clone)).await {
+        if let Ok(p) = fetch_and_cache(opts_clone, Some(fetch_state_clone)).await {
             let mut slot = updated_data_clone.lock().unwrap();
             *slot = Some(p);
         }
@@ -74,11 +115,50 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let mut app = App::new(pokemons);
     app.fetch_state = Some(fetch_state.clone());
+    app.sprite_variant.generation = sprite_generation;
+    app.palette = Palette {
+        depth: args
+            .iter()
+            .find_map(|a| a.strip_prefix("--colors="))
+            .and_then(ColorDepth::parse)
+            .unwrap_or_else(ColorDepth::detect),
+        dither: args.iter().any(|a| a == "--dither")
+            || std::env::var("POKEMON_DITHER").is_ok_and(|v| v == "1"),
+    };
+    // Graphics detection only looks at environment variables (no terminal
+    // query); `--graphics=kitty|sixel|none` forces the protocol.
+    app.graphics = match args.iter().find_map(|a| a.strip_prefix("--graphics=")) {
+        Some(forced) => GraphicsProtocol::parse(forced),
+        None => GraphicsProtocol::detect(),
+    };
+    app.render_mode = RenderMode::initial(app.graphics);
+    if let Ok(bg) = std::env::var("POKEMON_SPRITE_BG") {
+        match SpriteBackground::parse(&bg) {
+            Some(bg) => app.sprite_bg = bg,
+            None => eprintln!("Ignoring invalid POKEMON_SPRITE_BG={}", bg),
+        }
+    }
+
+    // Sprites are decoded on a background thread; thumbnails around the
+    // selection are prefetched into an LRU cache capped at `POKEMON_SPRITE_CACHE_MB` (default 8 MiB).
+    let cache_budget = std::env::var("POKEMON_SPRITE_CACHE_MB")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .map(|mb| mb * 1024 * 1024)
+        .unwrap_or(DEFAULT_CACHE_BUDGET);
+    app.disk_cache = DiskCache::open(DISK_CACHE_PATH).ok().map(Arc::new);
+    app.load_presets();
+    app.search_history = history::load();
+    app.start_loader(cache_budget);
 
     let tick_rate = Duration::from_millis(200);
+    // While a sprite is being decoded, input is polled in short slices so the
+    // finished sprite is drawn right away instead of on the next tick.
+    let loading_poll = Duration::from_millis(16);
     let mut last_tick = Instant::now();
 
     loop {
+        app.prefetch_neighbors();
         draw_ui(&mut terminal, &mut app)?;
 
         // If a background fetch has produced updated data, pick it up and refresh the app
@@ -86,79 +166,126 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let mut slot = updated_data.lock().unwrap();
             slot.take()
         } {
-            app.all_pokemons = new;
-            app.apply_filter();
-
-            // Preload compact thumbnails into the in-memory cache on a background
-            // thread so the first time the user views a Pokémon the UI doesn't
-            // block waiting for disk I/O. We generate small RGB thumbnails to
-            // keep memory usage low.
-            let cache_arc = app.sprite_cache.clone();
-            let ids: Vec<u32> = app.all_pokemons.iter().map(|p| p.pokedex).collect();
-            std::thread::spawn(move || {
-                const THUMB_W: u32 = 48;
-                const THUMB_H: u32 = 48;
-                for id in ids {
-                    let path = format!("data/sprites/{}.png", id);
-                    if let Ok(img) = image::open(&path) {
-                        let small = image::imageops::resize(&img.to_rgba8(), THUMB_W, THUMB_H, image::imageops::FilterType::Lanczos3);
-                        let mut pixels = Vec::with_capacity((THUMB_W * THUMB_H * 3) as usize);
-                        for y in 0..small.height() {
-                            for x in 0..small.width() {
-                                let p = small.get_pixel(x, y);
-                                pixels.push(p[0]);
-                                pixels.push(p[1]);
-                                pixels.push(p[2]);
-                            }
-                        }
-                        let thumb = SpriteThumb { w: THUMB_W, h: THUMB_H, pixels };
-                        let mut cache = cache_arc.lock().unwrap();
-                        cache.insert(id, thumb);
-                    }
-                }
-            });
+            app.set_pokemons(new);
         }
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
+        let timeout = if app.sprite_loading {
+            timeout.min(loading_poll)
+        } else {
+            timeout
+        };
         if event::poll(timeout)? {
             if let CEvent::Key(key) = event::read()? {
-                if app.search_mode {
+                if app.calc_editing {
                     match key.code {
-                        KeyCode::Enter | KeyCode::Esc => {
-                            app.search_mode = false;
+                        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('c') => {
+                            app.calc_editing = false;
+                        }
+                        KeyCode::Up => app.calc_move(-1),
+                        KeyCode::Down => app.calc_move(1),
+                        KeyCode::Left => app.calc_adjust(-1),
+                        KeyCode::Right => app.calc_adjust(1),
+                        KeyCode::Home => app.calc_extreme(false),
+                        KeyCode::End => app.calc_extreme(true),
+                        KeyCode::Backspace => app.calc_backspace(),
+                        KeyCode::Char(c) => {
+                            if let Some(d) = c.to_digit(10) {
+                                app.calc_digit(d);
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if let Some(picker) = app.preset_picker.as_mut() {
+                    if let Some(name) = picker.naming.as_mut() {
+                        match key.code {
+                            KeyCode::Enter => app.finish_saving_preset(),
+                            KeyCode::Esc => picker.naming = None,
+                            KeyCode::Backspace => {
+                                name.pop();
+                            }
+                            KeyCode::Char(c) => name.push(c),
+                            _ => {}
+                        }
+                    } else {
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('P') => app.preset_picker = None,
+                            KeyCode::Up => app.preset_move(-1),
+                            KeyCode::Down => app.preset_move(1),
+                            KeyCode::Enter => app.apply_preset(),
+                            KeyCode::Char('s') => app.start_saving_preset(),
+                            KeyCode::Char('d') | KeyCode::Delete => app.delete_preset(),
+                            _ => {}
+                        }
+                    }
+                } else if let Some(input) = app.goto_input.as_mut() {
+                    match key.code {
+                        KeyCode::Enter => app.finish_goto(),
+                        KeyCode::Esc => {
+                            app.goto_input = None;
+                            app.goto_error = None;
                         }
                         KeyCode::Backspace => {
-                            app.search_query.pop();
-                            app.apply_filter();
+                            input.pop();
+                            app.goto_error = None;
                         }
-                        KeyCode::Char(c) => {
-                            app.search_query.push(c);
-                            app.apply_filter();
+                        KeyCode::Char(c) if c.is_ascii_digit() && input.len() < 4 => {
+                            input.push(c);
+                            app.goto_error = None;
                         }
                         _ => {}
                     }
+                } else if app.search_mode {
+                    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                    let ctrl_r = ctrl && key.code == KeyCode::Char('r');
+                    if app.reverse_search.is_some() {
+                        match key.code {
+                            _ if ctrl_r => app.reverse_search_older(),
+                            KeyCode::Enter => app.accept_reverse_search(),
+                            KeyCode::Esc => app.cancel_reverse_search(),
+                            KeyCode::Backspace => app.reverse_search_pop(),
+                            KeyCode::Char(c) => app.reverse_search_push(c),
+                            _ => {}
+                        }
+                    } else {
+                        match key.code {
+                            _ if ctrl_r => app.reverse_search_older(),
+                            KeyCode::Char('u') if ctrl => app.clear_search(),
+                            KeyCode::Enter | KeyCode::Esc => app.finish_search(),
+                            KeyCode::Up => app.recall_history(true),
+                            KeyCode::Down => app.recall_history(false),
+                            KeyCode::Backspace => app.search_pop(),
+                            KeyCode::Char(c) => app.search_push(c),
+                            _ => {}
+                        }
+                    }
+                } else if app.show_help {
+                    match key.code {
+                        KeyCode::Char('q') => break,
+                        KeyCode::F(1) | KeyCode::Char('h') | KeyCode::Esc => app.toggle_help(),
+                        KeyCode::Up => app.scroll_help(-1),
+                        KeyCode::Down => app.scroll_help(1),
+                        KeyCode::PageUp => app.scroll_help(-10),
+                        KeyCode::PageDown => app.scroll_help(10),
+                        _ => {}
+                    }
                 } else {
                     match key.code {
                         KeyCode::Char('q') => break,
-                        KeyCode::F(1) | KeyCode::Char('h') => {
-                            app.show_help = !app.show_help;
-                        }
-                        KeyCode::Char('/') => {
-                            app.search_mode = true;
-                            app.search_query.clear();
-                            app.apply_filter();
-                        }
+                        KeyCode::F(1) | KeyCode::Char('h') => app.toggle_help(),
+                        KeyCode::F(12) => app.show_debug = !app.show_debug,
+                        KeyCode::Char('/') => app.start_search(),
                         KeyCode::Down => app.next(),
                         KeyCode::Char('r') => {
                             // Trigger a refresh: spawn a background fetch that writes into updated_data
                             let fetch_state_clone2 = fetch_state.clone();
                             let updated_data_clone2 = updated_data.clone();
-                            let fl_clone2 = fetch_limit;
+                            let opts_clone2 = fetch_opts.clone();
                             tokio::spawn(async move {
                                 if let Ok(p) =
-                                    fetch_and_cache(fl_clone2, Some(fetch_state_clone2)).await
+                                    fetch_and_cache(opts_clone2, Some(fetch_state_clone2)).await
                                 {
                                     let mut slot = updated_data_clone2.lock().unwrap();
                                     *slot = Some(p);
@@ -166,6 +293,35 @@ async fn main() -> Result<(), Box<dyn Error>> {
                             });
                         }
                         KeyCode::Up => app.previous(),
+                        KeyCode::Tab => app.next_tab(),
+                        KeyCode::BackTab => app.previous_tab(),
+                        KeyCode::Char('v') => app.cycle_encounter_version(),
+                        KeyCode::Char('d') => app.cycle_dex(),
+                        KeyCode::Char('s') => {
+                            app.sprite_variant.shiny = !app.sprite_variant.shiny;
+                            app.anim_frame = 0;
+                        }
+                        KeyCode::Char('b') => app.sprite_variant.back = !app.sprite_variant.back,
+                        KeyCode::Char('f') => {
+                            app.sprite_variant.female = !app.sprite_variant.female
+                        }
+                        KeyCode::Char('a') => {
+                            app.sprite_variant.artwork = !app.sprite_variant.artwork
+                        }
+                        KeyCode::Char('m') => app.render_mode = app.render_mode.next(app.graphics),
+                        KeyCode::Char('p') => app.anim_paused = !app.anim_paused,
+                        KeyCode::Char('n') => app.stat_scale = app.stat_scale.next(),
+                        KeyCode::Char('c') => app.edit_calculator(),
+                        KeyCode::Char('x') => app.cycle_radar_overlay(),
+                        KeyCode::Char('X') => app.pin_radar(),
+                        KeyCode::Char(' ') => app.toggle_mark(),
+                        KeyCode::Char('C') => app.compare_mode = !app.compare_mode,
+                        KeyCode::Char('o') => app.cycle_sort(),
+                        KeyCode::Char('O') => app.flip_sort(),
+                        KeyCode::Char('g') => app.goto_input = Some(String::new()),
+                        KeyCode::Char('P') => app.open_presets(),
+                        KeyCode::PageDown => app.scroll_detail(5),
+                        KeyCode::PageUp => app.scroll_detail(-5),
                         _ => {}
                     }
                 }
@@ -173,6 +329,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
 
         if last_tick.elapsed() >= tick_rate {
+            app.on_tick(last_tick.elapsed());
             last_tick = Instant::now();
         }
     }
@@ -22,6 +22,61 @@ pub struct Pokemon {
     pub base_experience: u32,
     #[serde(default)]
     pub stats: Vec<Stat>,
+    /// Wild encounters from `/pokemon/{id}/encounters`. `None` means they have
+    /// not been fetched yet; `Some(vec![])` means the Pokémon cannot be caught.
+    #[serde(default)]
+    pub encounters: Option<Vec<Encounter>>,
+    /// Regional Pokédex entries from the species `pokedex_numbers` (including
+    /// the national dex). Empty until the species has been fetched.
+    #[serde(default)]
+    pub dex_numbers: Vec<DexNumber>,
+    /// Keys of the optional sprite variants saved under `data/sprites/{key}/`.
+    /// `None` means variants have not been downloaded.
+    #[serde(default)]
+    pub sprite_variants: Option<Vec<String>>,
+    /// Generations (1..=8) whose sprite set from `sprites.versions` has been
+    /// downloaded under `data/sprites/gen-{n}/`.
+    #[serde(default)]
+    pub sprite_generations: Vec<u8>,
+    /// Generations in `sprite_generations` whose shiny, back and female
+    /// sprites have been downloaded too.
+    #[serde(default)]
+    pub sprite_generation_variants: Vec<u8>,
+    /// Keys (`front_default`, `front_shiny`) of the animated sprites saved under
+    /// `data/sprites/animated/`. `None` means they have not been downloaded.
+    #[serde(default)]
+    pub animated_sprites: Option<Vec<String>>,
+    /// Generation the species was introduced in, from the species resource.
+    /// `None` until the species has been fetched.
+    #[serde(default)]
+    pub generation: Option<u8>,
+    #[serde(default)]
+    pub legendary: bool,
+    #[serde(default)]
+    pub mythical: bool,
+    /// Names of the moves the Pokémon can learn, for full-text search. `None`
+    /// until fetched.
+    #[serde(default)]
+    pub moves: Option<Vec<String>>,
+}
+
+impl Pokemon {
+    /// Sum of the base stats (BST).
+    pub fn base_stat_total(&self) -> u32 {
+        self.stats.iter().map(|s| s.base).sum()
+    }
+
+    /// Entry number of this Pokémon in the Pokédex named `dex` (PokeAPI name,
+    /// e.g. `kanto`). The national number is always available.
+    pub fn dex_number(&self, dex: &str) -> Option<u32> {
+        if dex == "national" {
+            return Some(self.pokedex);
+        }
+        self.dex_numbers
+            .iter()
+            .find(|d| d.dex == dex)
+            .map(|d| d.number)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -31,3 +86,46 @@ pub struct Stat {
     #[serde(default)]
     pub base: u32,
 }
+
+/// One way of catching a Pokémon in a given game version. Encounter slots that
+/// share area, version and method are merged into a single level range with a
+/// summed chance.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct Encounter {
+    #[serde(default)]
+    pub location_area: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub method: String,
+    #[serde(default)]
+    pub min_level: u32,
+    #[serde(default)]
+    pub max_level: u32,
+    /// Encounter chance in percent.
+    #[serde(default)]
+    pub chance: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct DexNumber {
+    /// PokeAPI pokedex name, e.g. `kanto` or `original-johto`.
+    #[serde(default)]
+    pub dex: String,
+    #[serde(default)]
+    pub number: u32,
+}
+
+/// Pokédexes offered by the dex selector as (PokeAPI name, label).
+pub const DEXES: &[(&str, &str)] = &[
+    ("national", "National"),
+    ("kanto", "Kanto"),
+    ("original-johto", "Johto"),
+    ("hoenn", "Hoenn"),
+    ("original-sinnoh", "Sinnoh"),
+    ("original-unova", "Unova"),
+    ("kalos-central", "Kalos"),
+    ("original-alola", "Alola"),
+    ("galar", "Galar"),
+    ("paldea", "Paldea"),
+];
@@ -0,0 +1,148 @@
+//! Saved searches ("presets"): named queries kept in `presets.json` in the
+//! config directory. An applied preset filters the list together with the
+//! live search query.
+
+use crate::utils::config_dir;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+const FILE_NAME: &str = "presets.json";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    /// Search in the query language (see `query`).
+    pub query: String,
+}
+
+/// Presets offered until the user saves their own.
+const DEFAULTS: &[(&str, &str)] = &[
+    ("Gen 1 non-legendaries", "gen:1 -legendary -mythical"),
+    ("Legendaries and mythicals", "legendary OR mythical"),
+    ("Fast attackers", "spe>=100 (atk>=100 OR spa>=100)"),
+];
+
+fn path() -> Option<PathBuf> {
+    config_dir().map(|d| d.join(FILE_NAME))
+}
+
+fn defaults() -> Vec<Preset> {
+    DEFAULTS
+        .iter()
+        .map(|(name, query)| Preset {
+            name: name.to_string(),
+            query: query.to_string(),
+        })
+        .collect()
+}
+
+/// Saved presets, or the defaults when none have been saved. A file that
+/// can't be read or parsed is an error, so it isn't overwritten by a save.
+pub fn load() -> Result<Vec<Preset>, String> {
+    match path() {
+        Some(path) => load_from(&path),
+        None => Ok(defaults()),
+    }
+}
+
+fn load_from(path: &Path) -> Result<Vec<Preset>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", FILE_NAME, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(defaults()),
+        Err(e) => Err(format!("{}: {}", FILE_NAME, e)),
+    }
+}
+
+/// Add `preset`, replacing the one with the same name. Returns its index.
+pub fn upsert(presets: &mut Vec<Preset>, preset: Preset) -> usize {
+    match presets.iter().position(|p| p.name == preset.name) {
+        Some(i) => {
+            presets[i] = preset;
+            i
+        }
+        None => {
+            presets.push(preset);
+            presets.len() - 1
+        }
+    }
+}
+
+pub fn save(presets: &[Preset]) -> io::Result<()> {
+    let path =
+        path().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(presets).map_err(io::Error::other)?;
+    std::fs::write(path, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "dextui-presets-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn preset(name: &str, query: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            query: query.to_string(),
+        }
+    }
+
+    #[test]
+    fn missing_file_gives_defaults() {
+        let path = temp_path("missing");
+        let presets = load_from(&path).unwrap();
+        assert_eq!(presets.len(), DEFAULTS.len());
+        assert_eq!(presets[0], preset(DEFAULTS[0].0, DEFAULTS[0].1));
+    }
+
+    #[test]
+    fn saved_presets_replace_the_defaults() {
+        let path = temp_path("saved");
+        std::fs::write(&path, r#"[{"name": "Water", "query": "type:water"}]"#).unwrap();
+        assert_eq!(load_from(&path), Ok(vec![preset("Water", "type:water")]));
+        std::fs::write(&path, "[]").unwrap();
+        assert_eq!(load_from(&path), Ok(Vec::new()));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn broken_file_is_an_error() {
+        let path = temp_path("broken");
+        std::fs::write(&path, r#"[{"name": "Water", "query": "type:water"},]"#).unwrap();
+        let err = load_from(&path).unwrap_err();
+        assert!(err.starts_with("presets.json: "), "{}", err);
+        std::fs::write(&path, r#"{"name": "Water"}"#).unwrap();
+        assert!(load_from(&path).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn upsert_replaces_by_name() {
+        let mut presets = vec![preset("Fast", "spe>100"), preset("Water", "type:water")];
+        assert_eq!(
+            upsert(&mut presets, preset("Water", "type:water -legendary")),
+            1
+        );
+        assert_eq!(upsert(&mut presets, preset("Ice", "type:ice")), 2);
+        assert_eq!(
+            presets,
+            vec![
+                preset("Fast", "spe>100"),
+                preset("Water", "type:water -legendary"),
+                preset("Ice", "type:ice"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,664 @@
+//! Search query language.
+//!
+//! A query is a list of terms combined with `AND` (or just juxtaposition),
+//! `OR` / `|` and `NOT` / `-` / `!`, with parentheses for grouping:
+//!
+//! ```text
+//! type:fire type:flying spe>100 bst>=500 ability:levitate gen:3 -legendary
+//! (type:water OR type:ice) NOT gen:1
+//! ```
+//!
+//! `a b OR c` means `(a AND b) OR c`. A leading `-` always negates the word,
+//! so `-151` excludes #151 rather than meaning "up to 151".
+//!
+//! Terms are `field:value` matches (`type`, `ability`, `name`), numeric
+//! comparisons (`hp`, `atk`, `def`, `spa`, `spd`, `spe`, `bst`, `height`,
+//! `weight`, `exp`, `gen` with `<`, `<=`, `>`, `>=`, `=`, `!=` or `:`), the
+//! flags `legendary` and `mythical`, dex numbers and ranges (`#25`, `1-151`,
+//! numbered like the list), and bare words, which fuzzy-match names (see
+//! `fuzzy`), match types as a substring and find words of descriptions,
+//! abilities and moves (see `textindex`).
+
+use crate::fuzzy::fuzzy_match;
+use crate::models::Pokemon;
+use crate::textindex::TextIndex;
+use crate::types::TYPES;
+use crate::utils::format_name;
+
+/// Parsed search query.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+    /// Bare word: fuzzy match on the name, substring of a type, or one of the
+    /// Pokédex ids (sorted) the `TextIndex` finds it for; see `resolve_text`.
+    Text(String, Vec<u32>),
+    Name(String),
+    Type(String),
+    Ability(String),
+    Legendary,
+    Mythical,
+    Compare(NumField, Op, u32),
+    /// Dex number within this inclusive range, in the dex the list shows.
+    Number(u32, u32),
+}
+
+/// Numeric property a term can compare.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumField {
+    /// Base stat by PokeAPI name.
+    Stat(&'static str),
+    Bst,
+    Height,
+    Weight,
+    BaseExperience,
+    Generation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn apply(self, a: u32, b: u32) -> bool {
+        match self {
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+        }
+    }
+}
+
+const NUM_FIELDS: &[(&str, NumField)] = &[
+    ("hp", NumField::Stat("hp")),
+    ("atk", NumField::Stat("attack")),
+    ("attack", NumField::Stat("attack")),
+    ("def", NumField::Stat("defense")),
+    ("defense", NumField::Stat("defense")),
+    ("spa", NumField::Stat("special-attack")),
+    ("spatk", NumField::Stat("special-attack")),
+    ("spd", NumField::Stat("special-defense")),
+    ("spdef", NumField::Stat("special-defense")),
+    ("spe", NumField::Stat("speed")),
+    ("speed", NumField::Stat("speed")),
+    ("bst", NumField::Bst),
+    ("height", NumField::Height),
+    ("weight", NumField::Weight),
+    ("exp", NumField::BaseExperience),
+    ("gen", NumField::Generation),
+];
+
+const TEXT_FIELDS: &[&str] = &["type", "ability", "name"];
+
+/// Lowercase `s` and drop everything but letters and digits, so that
+/// `Lightning Rod`, `lightning-rod` and `lightningrod` compare equal.
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Query {
+    /// Parse `input`. An empty query parses to `None` (everything matches).
+    pub fn parse(input: &str) -> Result<Option<Query>, String> {
+        let tokens = tokenize(input);
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(Some(query)),
+            Some(Token::Close) => Err("unmatched ')'".to_string()),
+            Some(tok) => Err(format!("unexpected '{}'", tok)),
+        }
+    }
+
+    /// Whether `p` matches, with numbers looked up in the Pokédex named `dex`.
+    pub fn matches(&self, p: &Pokemon, dex: &str) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(p, dex) && b.matches(p, dex),
+            Query::Or(a, b) => a.matches(p, dex) || b.matches(p, dex),
+            Query::Not(q) => !q.matches(p, dex),
+            Query::Term(t) => t.matches(p, dex),
+        }
+    }
+
+    fn text_terms<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Query::And(a, b) | Query::Or(a, b) => {
+                a.text_terms(out);
+                b.text_terms(out);
+            }
+            Query::Term(Term::Text(q, _)) => out.push(q),
+            Query::Not(_) | Query::Term(_) => {}
+        }
+    }
+
+    /// Bare words the results must match (those not under a NOT).
+    pub fn text_words(&self) -> Vec<&str> {
+        let mut terms = Vec::new();
+        self.text_terms(&mut terms);
+        terms
+    }
+
+    /// Look up the bare words in `index`; they match no descriptions, abilities
+    /// or moves until this is called.
+    pub fn resolve_text(&mut self, index: &TextIndex) {
+        match self {
+            Query::And(a, b) | Query::Or(a, b) => {
+                a.resolve_text(index);
+                b.resolve_text(index);
+            }
+            Query::Not(q) => q.resolve_text(index),
+            Query::Term(Term::Text(q, ids)) => *ids = index.search(q),
+            Query::Term(_) => {}
+        }
+    }
+
+    /// Whether results should be ranked by `relevance`: the query has words
+    /// to fuzzy-match.
+    pub fn ranked(&self) -> bool {
+        !self.text_words().is_empty()
+    }
+
+    /// Sum of the fuzzy scores of the query's words against the displayed
+    /// `name`. Pokémon only matched by type score 0.
+    pub fn relevance(&self, name: &str) -> i32 {
+        self.text_words()
+            .iter()
+            .filter_map(|t| fuzzy_match(t, name))
+            .map(|m| m.score)
+            .sum()
+    }
+
+    /// Char indices of the displayed `name` matched by the query's words.
+    pub fn highlights(&self, name: &str) -> Vec<usize> {
+        let mut positions: Vec<usize> = self
+            .text_words()
+            .iter()
+            .filter_map(|t| fuzzy_match(t, name))
+            .flat_map(|m| m.positions)
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+impl Term {
+    fn matches(&self, p: &Pokemon, dex: &str) -> bool {
+        match self {
+            Term::Text(q, ids) => {
+                fuzzy_match(q, &format_name(&p.name)).is_some()
+                    || p.types.iter().any(|t| t.to_lowercase().contains(q))
+                    || ids.binary_search(&p.pokedex).is_ok()
+            }
+            Term::Name(q) => normalize(&p.name).contains(q),
+            Term::Type(t) => p.types.iter().any(|pt| pt.eq_ignore_ascii_case(t)),
+            Term::Ability(q) => p.abilities.iter().any(|a| normalize(a).contains(q)),
+            Term::Legendary => p.legendary,
+            Term::Mythical => p.mythical,
+            Term::Compare(field, op, value) => {
+                let actual = match field {
+                    NumField::Stat(name) => {
+                        p.stats.iter().find(|s| s.name == *name).map(|s| s.base)
+                    }
+                    NumField::Bst => Some(p.base_stat_total()),
+                    NumField::Height => Some(p.height),
+                    NumField::Weight => Some(p.weight),
+                    NumField::BaseExperience => Some(p.base_experience),
+                    NumField::Generation => p.generation.map(u32::from),
+                };
+                // Unknown values (e.g. species not fetched yet) never match.
+                actual.is_some_and(|a| op.apply(a, *value))
+            }
+            Term::Number(lo, hi) => p.dex_number(dex).is_some_and(|n| (*lo..=*hi).contains(&n)),
+        }
+    }
+
+    /// Parse a single word such as `type:fire`, `spe>100`, `#25` or `levitate`.
+    fn parse(word: &str) -> Result<Query, String> {
+        if let Some(range) = parse_number_range(word)? {
+            return Ok(Query::Term(range));
+        }
+        let split = match word.find(['<', '>', '=', '!', ':']) {
+            Some(i) => i,
+            None => {
+                let term = match word.to_lowercase().as_str() {
+                    "legendary" => Term::Legendary,
+                    "mythical" => Term::Mythical,
+                    w => Term::Text(w.to_string(), Vec::new()),
+                };
+                return Ok(Query::Term(term));
+            }
+        };
+        let field = word[..split].to_lowercase();
+        let rest = &word[split..];
+        let (op, value) = [
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("!=", Op::Ne),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+            ("=", Op::Eq),
+            (":", Op::Eq),
+        ]
+        .iter()
+        .find_map(|(sym, op)| rest.strip_prefix(sym).map(|v| (*op, v)))
+        .ok_or_else(|| format!("unexpected '{}' in '{}'", &rest[..1], word))?;
+        if field.is_empty() {
+            return Err(format!("missing field before '{}'", rest));
+        }
+        if value.is_empty() {
+            return Err(format!("missing value after '{}'", word));
+        }
+
+        if let Some(&(_, num)) = NUM_FIELDS.iter().find(|(name, _)| *name == field) {
+            let n = match num {
+                NumField::Generation => parse_gen(value),
+                _ => value.parse().ok(),
+            }
+            .ok_or_else(|| format!("'{}' needs a number, not '{}'", field, value))?;
+            return Ok(Query::Term(Term::Compare(num, op, n)));
+        }
+        if !TEXT_FIELDS.contains(&field.as_str()) {
+            return Err(format!(
+                "unknown field '{}' (try type, ability, name, hp, atk, def, spa, spd, spe, bst, gen)",
+                field
+            ));
+        }
+        let negate = match op {
+            Op::Eq => false,
+            Op::Ne => true,
+            _ => return Err(format!("'{}' can only be matched with ':' or '!='", field)),
+        };
+        let term = match field.as_str() {
+            "type" => {
+                let t = value.to_lowercase();
+                if !TYPES.contains(&t.as_str()) {
+                    return Err(format!("unknown type '{}'", value));
+                }
+                Term::Type(t)
+            }
+            "ability" => Term::Ability(normalize(value)),
+            _ => Term::Name(normalize(value)),
+        };
+        let q = Query::Term(term);
+        Ok(if negate { Query::Not(Box::new(q)) } else { q })
+    }
+}
+
+/// Dex number or range: `25`, `#25`, `1-151` or `#1-151` (either order).
+/// `None` if `word` isn't one; an error for a `#` not followed by one.
+fn parse_number_range(word: &str) -> Result<Option<Term>, String> {
+    let (hash, body) = match word.strip_prefix('#') {
+        Some(body) => (true, body),
+        None => (false, word),
+    };
+    let number = |s: &str| {
+        (!s.is_empty() && s.chars().all(|c| c.is_ascii_digit()))
+            .then(|| s.parse::<u32>().ok())
+            .flatten()
+    };
+    let range = match body.split_once('-') {
+        Some((a, b)) => number(a).zip(number(b)),
+        None => number(body).map(|n| (n, n)),
+    };
+    match range {
+        Some((a, b)) => Ok(Some(Term::Number(a.min(b), a.max(b)))),
+        None if hash => Err(format!("'{}' needs a number, e.g. #25 or #1-151", word)),
+        None => Ok(None),
+    }
+}
+
+/// Generation as a number (`3`) or Roman numeral (`iii`).
+fn parse_gen(s: &str) -> Option<u32> {
+    const NUMERALS: [&str; 9] = ["i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix"];
+    s.parse().ok().or_else(|| {
+        NUMERALS
+            .iter()
+            .position(|n| n.eq_ignore_ascii_case(s))
+            .map(|i| i as u32 + 1)
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Word(w) => write!(f, "{}", w),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, tokens: &mut Vec<Token>| {
+        if word.is_empty() {
+            return;
+        }
+        tokens.push(match word.to_uppercase().as_str() {
+            "AND" | "&" => Token::And,
+            "OR" | "|" => Token::Or,
+            "NOT" => Token::Not,
+            _ => Token::Word(std::mem::take(word)),
+        });
+        word.clear();
+    };
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                flush(&mut word, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            c if c.is_whitespace() => flush(&mut word, &mut tokens),
+            c => word.push(c),
+        }
+    }
+    flush(&mut word, &mut tokens);
+    tokens
+}
+
+/// Recursive descent parser; precedence from loosest to tightest is OR, AND,
+/// NOT.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let tok = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut q = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            let rhs = self.and().map_err(|e| format!("after OR: {}", e))?;
+            q = Query::Or(Box::new(q), Box::new(rhs));
+        }
+        Ok(q)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut q = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.pos += 1;
+                    let rhs = self.not().map_err(|e| format!("after AND: {}", e))?;
+                    q = Query::And(Box::new(q), Box::new(rhs));
+                }
+                // Juxtaposed terms are ANDed.
+                Some(Token::Word(_)) | Some(Token::Not) | Some(Token::Open) => {
+                    let rhs = self.not()?;
+                    q = Query::And(Box::new(q), Box::new(rhs));
+                }
+                _ => return Ok(q),
+            }
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(
+                self.not().map_err(|e| format!("after NOT: {}", e))?,
+            ))),
+            Some(Token::Open) => {
+                let q = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(q),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Word(w)) => match w.strip_prefix(['-', '!']) {
+                Some(rest) if !rest.is_empty() => Ok(Query::Not(Box::new(Term::parse(rest)?))),
+                _ => Term::parse(&w),
+            },
+            Some(tok) => Err(format!("expected a term, found '{}'", tok)),
+            None => Err("expected a term".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DexNumber;
+
+    fn parse(input: &str) -> Query {
+        Query::parse(input).unwrap().unwrap()
+    }
+
+    fn term(word: &str) -> Box<Query> {
+        Box::new(parse(word))
+    }
+
+    fn pokemon(name: &str, pokedex: u32, types: &[&str]) -> Pokemon {
+        Pokemon {
+            name: name.to_string(),
+            pokedex,
+            types: types.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(Query::parse(""), Ok(None));
+        assert_eq!(Query::parse("   "), Ok(None));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let a = term("type:fire");
+        let b = term("type:flying");
+        let c = term("type:ice");
+        let expected = Query::Or(Box::new(Query::And(a.clone(), b.clone())), c.clone());
+        assert_eq!(parse("type:fire type:flying OR type:ice"), expected);
+        assert_eq!(parse("type:fire AND type:flying | type:ice"), expected);
+        assert_eq!(
+            parse("type:fire (type:flying OR type:ice)"),
+            Query::And(a, Box::new(Query::Or(b, c)))
+        );
+    }
+
+    #[test]
+    fn not_binds_tightest() {
+        assert_eq!(
+            parse("NOT type:fire type:ice"),
+            Query::And(Box::new(Query::Not(term("type:fire"))), term("type:ice"))
+        );
+        assert_eq!(parse("!legendary"), Query::Not(term("legendary")));
+        assert_eq!(parse("type!=fire"), Query::Not(term("type:fire")));
+    }
+
+    #[test]
+    fn leading_minus_negates_numbers() {
+        assert_eq!(parse("-151"), Query::Not(term("#151")));
+        let mew = pokemon("mew", 151, &["psychic"]);
+        let pikachu = pokemon("pikachu", 25, &["electric"]);
+        let q = parse("-151");
+        assert!(!q.matches(&mew, "national"));
+        assert!(q.matches(&pikachu, "national"));
+    }
+
+    #[test]
+    fn matches_combined_terms() {
+        let charizard = pokemon("charizard", 6, &["fire", "flying"]);
+        let lapras = pokemon("lapras", 131, &["water", "ice"]);
+        let q = parse("(type:water OR type:fire) -type:ice");
+        assert!(q.matches(&charizard, "national"));
+        assert!(!q.matches(&lapras, "national"));
+    }
+
+    #[test]
+    fn unbalanced_parens() {
+        assert_eq!(Query::parse("(type:fire"), Err("missing ')'".to_string()));
+        assert_eq!(Query::parse("type:fire)"), Err("unmatched ')'".to_string()));
+        assert_eq!(
+            Query::parse("()"),
+            Err("expected a term, found ')'".to_string())
+        );
+    }
+
+    #[test]
+    fn dangling_operators() {
+        assert_eq!(
+            Query::parse("type:fire AND"),
+            Err("after AND: expected a term".to_string())
+        );
+        assert_eq!(
+            Query::parse("type:fire OR"),
+            Err("after OR: expected a term".to_string())
+        );
+        assert_eq!(
+            Query::parse("NOT"),
+            Err("after NOT: expected a term".to_string())
+        );
+        assert_eq!(
+            Query::parse("OR type:fire"),
+            Err("expected a term, found 'OR'".to_string())
+        );
+    }
+
+    #[test]
+    fn field_errors() {
+        assert_eq!(
+            Query::parse("colour:red"),
+            Err("unknown field 'colour' (try type, ability, name, hp, atk, def, spa, spd, spe, bst, gen)"
+                .to_string())
+        );
+        assert_eq!(
+            Query::parse("type:fairy type:sound"),
+            Err("unknown type 'sound'".to_string())
+        );
+        assert_eq!(
+            Query::parse("spe>fast"),
+            Err("'spe' needs a number, not 'fast'".to_string())
+        );
+        assert_eq!(
+            Query::parse(">100"),
+            Err("missing field before '>100'".to_string())
+        );
+        assert_eq!(
+            Query::parse("spe>"),
+            Err("missing value after 'spe>'".to_string())
+        );
+        assert_eq!(
+            Query::parse("type>fire"),
+            Err("'type' can only be matched with ':' or '!='".to_string())
+        );
+    }
+
+    #[test]
+    fn numeric_fields() {
+        assert_eq!(
+            parse("SPE>=100"),
+            Query::Term(Term::Compare(NumField::Stat("speed"), Op::Ge, 100))
+        );
+        assert_eq!(
+            parse("gen:iv"),
+            Query::Term(Term::Compare(NumField::Generation, Op::Eq, 4))
+        );
+        assert_eq!(
+            parse("ability:Lightning-Rod"),
+            Query::Term(Term::Ability("lightningrod".to_string()))
+        );
+    }
+
+    #[test]
+    fn number_ranges() {
+        assert_eq!(parse_number_range("#25"), Ok(Some(Term::Number(25, 25))));
+        assert_eq!(parse_number_range("25"), Ok(Some(Term::Number(25, 25))));
+        assert_eq!(parse_number_range("1-151"), Ok(Some(Term::Number(1, 151))));
+        assert_eq!(parse_number_range("#1-151"), Ok(Some(Term::Number(1, 151))));
+        assert_eq!(parse_number_range("151-1"), Ok(Some(Term::Number(1, 151))));
+        assert_eq!(parse_number_range("pikachu"), Ok(None));
+        assert_eq!(parse_number_range("1-2-3"), Ok(None));
+    }
+
+    #[test]
+    fn incomplete_number_ranges() {
+        for word in ["-", "1-", "-5", "1-x"] {
+            assert_eq!(parse_number_range(word), Ok(None), "{}", word);
+        }
+        assert_eq!(
+            parse_number_range("#-"),
+            Err("'#-' needs a number, e.g. #25 or #1-151".to_string())
+        );
+        assert_eq!(
+            parse_number_range("#"),
+            Err("'#' needs a number, e.g. #25 or #1-151".to_string())
+        );
+        assert_eq!(
+            Query::parse("#pikachu"),
+            Err("'#pikachu' needs a number, e.g. #25 or #1-151".to_string())
+        );
+    }
+
+    #[test]
+    fn number_overflow() {
+        assert_eq!(
+            parse_number_range("4294967295"),
+            Ok(Some(Term::Number(u32::MAX, u32::MAX)))
+        );
+        // Too big for a dex number: a bare word, or an error after '#'.
+        assert_eq!(parse_number_range("4294967296"), Ok(None));
+        assert_eq!(parse_number_range("1-99999999999"), Ok(None));
+        assert!(parse_number_range("#99999999999").is_err());
+    }
+
+    #[test]
+    fn numbers_use_the_shown_dex() {
+        let mut chikorita = pokemon("chikorita", 152, &["grass"]);
+        chikorita.dex_numbers.push(DexNumber {
+            dex: "original-johto".to_string(),
+            number: 1,
+        });
+        let q = parse("1-10");
+        assert!(!q.matches(&chikorita, "national"));
+        assert!(q.matches(&chikorita, "original-johto"));
+        assert!(!q.matches(&chikorita, "kanto"));
+        assert!(parse("#152").matches(&chikorita, "national"));
+    }
+}
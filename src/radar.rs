@@ -0,0 +1,112 @@
+//! Hexagonal radar chart of the six base stats, drawn with braille dots on a
+//! ratatui canvas.
+
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::Span;
+use ratatui::widgets::canvas::{Canvas, Context, Line};
+use ratatui::widgets::Block;
+
+/// Indices into `calc::STAT_NAMES` of the chart axes, clockwise from the top:
+/// HP, Attack, Defense, Speed, Sp. Def, Sp. Atk (the usual dex layout).
+pub const AXES: [usize; 6] = [0, 1, 2, 5, 4, 3];
+
+/// Radius of the outermost ring; labels are printed just outside it.
+const RADIUS: f64 = 1.0;
+const LABEL_RADIUS: f64 = 1.2;
+
+/// One polygon on the chart: a value per axis as a fraction (0..=1) of the
+/// outer ring, in `AXES` order.
+pub struct RadarSeries {
+    pub values: [f64; 6],
+    pub color: Color,
+}
+
+fn axis_point(axis: usize, r: f64) -> (f64, f64) {
+    let angle = std::f64::consts::FRAC_PI_2 - axis as f64 * std::f64::consts::PI / 3.0;
+    (r * angle.cos(), r * angle.sin())
+}
+
+fn polygon(ctx: &mut Context, radii: [f64; 6], color: Color) {
+    for axis in 0..6 {
+        let (x1, y1) = axis_point(axis, radii[axis]);
+        let (x2, y2) = axis_point((axis + 1) % 6, radii[(axis + 1) % 6]);
+        ctx.draw(&Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            color,
+        });
+    }
+}
+
+/// Canvas bounds for `area` that keep the hexagon regular: a terminal cell is
+/// about twice as tall as it is wide.
+fn bounds(area: Rect) -> ([f64; 2], [f64; 2]) {
+    let margin = LABEL_RADIUS + 0.2;
+    let cols = area.width.saturating_sub(2).max(1) as f64;
+    let rows = area.height.saturating_sub(2).max(1) as f64 * 2.0;
+    let (x, y) = if cols >= rows {
+        (margin * cols / rows, margin)
+    } else {
+        (margin, margin * rows / cols)
+    };
+    ([-x, x], [-y, y])
+}
+
+/// Radar chart for `area` with axis `labels` and `series` drawn in order, so
+/// the last one ends up on top.
+pub fn radar_chart<'a>(
+    block: Block<'a>,
+    area: Rect,
+    labels: [String; 6],
+    series: Vec<RadarSeries>,
+) -> Canvas<'a, impl Fn(&mut Context) + 'a> {
+    let (x_bounds, y_bounds) = bounds(area);
+    // Width of one terminal column in canvas units, for placing labels.
+    let col_w = (x_bounds[1] - x_bounds[0]) / area.width.saturating_sub(2).max(1) as f64;
+    Canvas::default()
+        .block(block)
+        .marker(Marker::Braille)
+        .x_bounds(x_bounds)
+        .y_bounds(y_bounds)
+        .paint(move |ctx| {
+            for ring in [0.25, 0.5, 0.75, 1.0] {
+                polygon(ctx, [RADIUS * ring; 6], Color::DarkGray);
+            }
+            for axis in 0..6 {
+                let (x2, y2) = axis_point(axis, RADIUS);
+                ctx.draw(&Line {
+                    x1: 0.0,
+                    y1: 0.0,
+                    x2,
+                    y2,
+                    color: Color::DarkGray,
+                });
+            }
+            for s in &series {
+                ctx.layer();
+                polygon(ctx, s.values.map(|v| v.clamp(0.0, 1.0) * RADIUS), s.color);
+            }
+            for (axis, label) in labels.iter().enumerate() {
+                let (x, y) = axis_point(axis, LABEL_RADIUS);
+                // Labels are printed from their left edge: right-align the
+                // ones on the left side and center the top and bottom ones.
+                let width = label.chars().count() as f64 * col_w;
+                let x = if x < -0.1 {
+                    x - width
+                } else if x < 0.1 {
+                    x - width / 2.0
+                } else {
+                    x
+                };
+                ctx.print(
+                    x,
+                    y,
+                    Span::styled(label.clone(), Style::default().fg(Color::Gray)),
+                );
+            }
+        })
+}
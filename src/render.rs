@@ -0,0 +1,437 @@
+//! Turning sprite pixels into styled terminal cells.
+
+use crate::graphics::GraphicsProtocol;
+use crate::sprites::{Pixel, ALPHA_THRESHOLD};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Span, Spans};
+
+/// How sprite pixels are mapped onto terminal cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderMode {
+    /// Two pixel rows per cell using `▀` with the upper pixel as foreground and
+    /// the lower pixel as background. Terminal cells are roughly twice as tall as
+    /// they are wide, so this gives square pixels at full cell resolution.
+    HalfBlock,
+    /// One pixel per two cells (`"  "` with a background color). Square pixels,
+    /// half the resolution, but works with fonts that render `▀` poorly.
+    Block,
+    /// True-resolution image via a terminal graphics protocol.
+    Graphics(GraphicsProtocol),
+}
+
+impl RenderMode {
+    /// Preferred mode: graphics when the terminal supports it, else half blocks.
+    pub fn initial(graphics: Option<GraphicsProtocol>) -> Self {
+        graphics.map_or(RenderMode::HalfBlock, RenderMode::Graphics)
+    }
+
+    /// Cycle half-block -> block -> graphics (if supported) -> half-block.
+    pub fn next(self, graphics: Option<GraphicsProtocol>) -> Self {
+        match (self, graphics) {
+            (RenderMode::HalfBlock, _) => RenderMode::Block,
+            (RenderMode::Block, Some(g)) => RenderMode::Graphics(g),
+            _ => RenderMode::HalfBlock,
+        }
+    }
+
+    /// Largest square sprite, in pixels, that fits in `cols` x `rows` cells.
+    pub fn fit(self, cols: u32, rows: u32) -> (u32, u32) {
+        let side = match self {
+            RenderMode::HalfBlock => cols.min(rows * 2),
+            RenderMode::Block | RenderMode::Graphics(_) => (cols / 2).min(rows),
+        }
+        .max(1);
+        (side, side)
+    }
+}
+
+/// What transparent sprite pixels are drawn as. Configured with
+/// `POKEMON_SPRITE_BG=default|checker|#rrggbb`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SpriteBackground {
+    /// The terminal's own background color.
+    Terminal,
+    /// A grey checkerboard, like image editors use.
+    Checker,
+    Solid(u8, u8, u8),
+}
+
+impl SpriteBackground {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "default" | "terminal" | "none" => Some(SpriteBackground::Terminal),
+            "checker" | "checkerboard" => Some(SpriteBackground::Checker),
+            hex => {
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                if hex.len() != 6 {
+                    return None;
+                }
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+                Some(SpriteBackground::Solid(
+                    channel(0)?,
+                    channel(2)?,
+                    channel(4)?,
+                ))
+            }
+        }
+    }
+
+    /// Background color at pixel (`x`, `y`), or `None` for the terminal default.
+    fn color_at(self, x: usize, y: usize, palette: &Palette) -> Option<Color> {
+        match self {
+            SpriteBackground::Terminal => None,
+            SpriteBackground::Checker => {
+                // 2x2 pixel squares read better than single-pixel noise.
+                if (x / 2 + y / 2).is_multiple_of(2) {
+                    Some(palette.color(68, 68, 68))
+                } else {
+                    Some(palette.color(102, 102, 102))
+                }
+            }
+            SpriteBackground::Solid(r, g, b) => Some(palette.color(r, g, b)),
+        }
+    }
+}
+
+/// Color of pixel `px` at (`x`, `y`), substituting the background for
+/// transparent pixels (`None` = terminal default).
+fn pixel_color(
+    px: Pixel,
+    x: usize,
+    y: usize,
+    bg: SpriteBackground,
+    palette: &Palette,
+) -> Option<Color> {
+    let (r, g, b, a) = px;
+    if a < ALPHA_THRESHOLD {
+        bg.color_at(x, y, palette)
+    } else {
+        Some(palette.color(r, g, b))
+    }
+}
+
+/// Build one `Spans` per terminal row for the pixel grid `rows` (row-major RGBA),
+/// quantized to `palette`.
+pub fn sprite_lines(
+    rows: &[Vec<Pixel>],
+    mode: RenderMode,
+    bg: SpriteBackground,
+    palette: &Palette,
+) -> Vec<Spans<'static>> {
+    let dithered;
+    let rows = if palette.dither && palette.depth != ColorDepth::TrueColor {
+        dithered = palette.dither(rows);
+        &dithered[..]
+    } else {
+        rows
+    };
+    match mode {
+        // Graphics protocols draw outside the cell buffer; see `graphics::sync`.
+        RenderMode::Graphics(_) => Vec::new(),
+        RenderMode::Block => rows
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                Spans::from(
+                    row.iter()
+                        .enumerate()
+                        .map(|(x, &px)| match pixel_color(px, x, y, bg, palette) {
+                            Some(c) => Span::styled("  ", Style::default().bg(c)),
+                            None => Span::raw("  "),
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect(),
+        RenderMode::HalfBlock => rows
+            .chunks(2)
+            .enumerate()
+            .map(|(cy, pair)| {
+                let (y_top, y_bottom) = (cy * 2, cy * 2 + 1);
+                Spans::from(
+                    pair[0]
+                        .iter()
+                        .enumerate()
+                        .map(|(x, &top)| {
+                            let top = pixel_color(top, x, y_top, bg, palette);
+                            let bottom = pair
+                                .get(1)
+                                .and_then(|l| l.get(x))
+                                .and_then(|&px| pixel_color(px, x, y_bottom, bg, palette));
+                            half_block_cell(top, bottom)
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect(),
+    }
+}
+
+/// One half-block cell. A transparent half keeps the terminal background, so
+/// the glyph is chosen such that only opaque halves are painted.
+fn half_block_cell(top: Option<Color>, bottom: Option<Color>) -> Span<'static> {
+    match (top, bottom) {
+        (Some(t), Some(b)) => Span::styled("▀", Style::default().fg(t).bg(b)),
+        (Some(t), None) => Span::styled("▀", Style::default().fg(t)),
+        (None, Some(b)) => Span::styled("▄", Style::default().fg(b)),
+        (None, None) => Span::raw(" "),
+    }
+}
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorDepth {
+    TrueColor,
+    /// xterm 256-color palette.
+    Ansi256,
+    /// The 16 standard ANSI colors.
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// `POKEMON_COLORS` (`truecolor`, `256`, `16`) wins; otherwise `COLORTERM`
+    /// advertising truecolor/24bit means full RGB and a `*256color` `TERM` means
+    /// the xterm-256 palette. Anything else gets the 16 ANSI colors.
+    pub fn detect() -> Self {
+        if let Some(d) = std::env::var("POKEMON_COLORS")
+            .ok()
+            .and_then(|s| Self::parse(&s))
+        {
+            return d;
+        }
+        let colorterm = std::env::var("COLORTERM")
+            .unwrap_or_default()
+            .to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        if std::env::var("TERM")
+            .unwrap_or_default()
+            .contains("256color")
+        {
+            return ColorDepth::Ansi256;
+        }
+        ColorDepth::Ansi16
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "truecolor" | "24bit" | "rgb" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Ansi256),
+            "16" => Some(ColorDepth::Ansi16),
+            _ => None,
+        }
+    }
+}
+
+/// xterm's default RGB values for the 16 ANSI colors, in index order.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const ANSI16_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Channel levels of the xterm 6x6x6 color cube (indices 16..=231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    // Weighted towards green like the eye; cheap stand-in for a perceptual metric.
+    2 * d(r1, r2) + 4 * d(g1, g2) + 3 * d(b1, b2)
+}
+
+/// Maps RGB colors onto what the terminal can display.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Palette {
+    pub depth: ColorDepth,
+    /// Floyd–Steinberg dither sprites before quantizing (ignored for truecolor).
+    pub dither: bool,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            depth: ColorDepth::TrueColor,
+            dither: false,
+        }
+    }
+}
+
+impl Palette {
+    /// Closest displayable color to `rgb`, with the RGB value it actually shows.
+    fn nearest(&self, rgb: (u8, u8, u8)) -> (Color, (u8, u8, u8)) {
+        match self.depth {
+            ColorDepth::TrueColor => (Color::Rgb(rgb.0, rgb.1, rgb.2), rgb),
+            ColorDepth::Ansi16 => {
+                let i = (0..16)
+                    .min_by_key(|&i| distance(rgb, ANSI16[i]))
+                    .unwrap_or(0);
+                (ANSI16_COLORS[i], ANSI16[i])
+            }
+            ColorDepth::Ansi256 => {
+                let level = |c: u8| {
+                    (0..6)
+                        .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+                        .unwrap_or(0)
+                };
+                let (ri, gi, bi) = (level(rgb.0), level(rgb.1), level(rgb.2));
+                let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+                let cube_idx = 16 + 36 * ri + 6 * gi + bi;
+                // Grey ramp 232..=255 covers 8, 18, ..., 238.
+                let avg = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+                let gi = (avg.saturating_sub(3) / 10).min(23) as u8;
+                let g = 8 + gi * 10;
+                if distance(rgb, (g, g, g)) < distance(rgb, cube) {
+                    (Color::Indexed(232 + gi), (g, g, g))
+                } else {
+                    (Color::Indexed(cube_idx as u8), cube)
+                }
+            }
+        }
+    }
+
+    pub fn color(&self, r: u8, g: u8, b: u8) -> Color {
+        self.nearest((r, g, b)).0
+    }
+
+    /// Snap every opaque pixel in `rows` to the palette, diffusing the
+    /// quantization error with Floyd–Steinberg. Transparent pixels neither
+    /// receive nor spread error.
+    pub fn dither(&self, rows: &[Vec<Pixel>]) -> Vec<Vec<Pixel>> {
+        let h = rows.len();
+        let w = rows.first().map_or(0, |r| r.len());
+        let mut err = vec![vec![[0f32; 3]; w + 2]; h + 1];
+        let mut out = rows.to_vec();
+        for y in 0..h {
+            for x in 0..w.min(rows[y].len()) {
+                let (r, g, b, a) = rows[y][x];
+                if a < ALPHA_THRESHOLD {
+                    continue;
+                }
+                let e = err[y][x + 1];
+                let want = [r as f32 + e[0], g as f32 + e[1], b as f32 + e[2]];
+                let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+                let (_, got) = self.nearest((clamp(want[0]), clamp(want[1]), clamp(want[2])));
+                out[y][x] = (got.0, got.1, got.2, a);
+                let diff = [
+                    want[0] - got.0 as f32,
+                    want[1] - got.1 as f32,
+                    want[2] - got.2 as f32,
+                ];
+                for (dx, dy, weight) in [(2, 0, 7.0), (0, 1, 3.0), (1, 1, 5.0), (2, 1, 1.0)] {
+                    let cell = &mut err[y + dy][x + dx];
+                    for c in 0..3 {
+                        cell[c] += diff[c] * weight / 16.0;
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSI16_PALETTE: Palette = Palette {
+        depth: ColorDepth::Ansi16,
+        dither: true,
+    };
+    const ANSI256_PALETTE: Palette = Palette {
+        depth: ColorDepth::Ansi256,
+        dither: true,
+    };
+
+    #[test]
+    fn palette_colors_map_to_themselves() {
+        for (i, &rgb) in ANSI16.iter().enumerate() {
+            assert_eq!(ANSI16_PALETTE.nearest(rgb), (ANSI16_COLORS[i], rgb));
+        }
+        assert_eq!(ANSI16_PALETTE.color(255, 0, 0), Color::LightRed);
+        assert_eq!(ANSI16_PALETTE.color(250, 10, 5), Color::LightRed);
+
+        assert_eq!(ANSI256_PALETTE.color(255, 0, 0), Color::Indexed(196));
+        assert_eq!(ANSI256_PALETTE.color(0, 0, 0), Color::Indexed(16));
+        assert_eq!(ANSI256_PALETTE.color(255, 255, 255), Color::Indexed(231));
+        assert_eq!(ANSI256_PALETTE.color(95, 135, 175), Color::Indexed(67));
+
+        let truecolor = Palette::default();
+        assert_eq!(truecolor.color(1, 2, 3), Color::Rgb(1, 2, 3));
+    }
+
+    #[test]
+    fn grays_use_the_gray_ramp() {
+        assert_eq!(
+            ANSI256_PALETTE.nearest((128, 128, 128)),
+            (Color::Indexed(244), (128, 128, 128))
+        );
+        assert_eq!(ANSI256_PALETTE.color(8, 8, 8), Color::Indexed(232));
+        assert_eq!(ANSI256_PALETTE.color(238, 238, 238), Color::Indexed(255));
+        // Slightly tinted grays still land on the ramp.
+        assert_eq!(ANSI256_PALETTE.color(60, 58, 62), Color::Indexed(237));
+    }
+
+    #[test]
+    fn dither_keeps_transparency() {
+        let rows = vec![
+            vec![(128, 128, 128, 255), (10, 20, 30, 0), (128, 128, 128, 255)],
+            vec![(10, 20, 30, 0), (200, 100, 50, 255), (0, 0, 0, 0)],
+        ];
+        let out = ANSI16_PALETTE.dither(&rows);
+        assert_eq!(out[0][1], (10, 20, 30, 0));
+        assert_eq!(out[1][0], (10, 20, 30, 0));
+        assert_eq!(out[1][2], (0, 0, 0, 0));
+        for &(r, g, b, a) in [out[0][0], out[0][2], out[1][1]].iter() {
+            assert_eq!(a, 255);
+            assert!(ANSI16.contains(&(r, g, b)));
+        }
+    }
+
+    #[test]
+    fn dither_leaves_palette_colors_alone() {
+        let rows = vec![vec![(205, 0, 0, 255); 4]; 3];
+        assert_eq!(ANSI16_PALETTE.dither(&rows), rows);
+    }
+
+    #[test]
+    fn parse_color_depth() {
+        assert_eq!(ColorDepth::parse("TrueColor"), Some(ColorDepth::TrueColor));
+        assert_eq!(ColorDepth::parse("24bit"), Some(ColorDepth::TrueColor));
+        assert_eq!(ColorDepth::parse(" 256 "), Some(ColorDepth::Ansi256));
+        assert_eq!(ColorDepth::parse("16"), Some(ColorDepth::Ansi16));
+        assert_eq!(ColorDepth::parse("8"), None);
+    }
+}
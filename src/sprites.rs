@@ -0,0 +1,704 @@
+//! Sprite variants, their on-disk layout and the compact thumbnails the UI renders.
+//!
+//! The default front sprite lives at `data/sprites/{id}.png` (the original cache
+//! layout). Optional variants are stored one directory per PokeAPI sprite key,
+//! e.g. `data/sprites/front_shiny/{id}.png` or `data/sprites/official-artwork/{id}.png`.
+//! Generation-scoped sets (`sprites.versions`) get their own root, e.g.
+//! `data/sprites/gen-1/{id}.png` and `data/sprites/gen-1/back_default/{id}.png`.
+
+use crate::diskcache::{sprite_hash, DiskCache};
+use image::imageops::FilterType;
+use std::path::PathBuf;
+
+pub const SPRITE_DIR: &str = "data/sprites";
+
+/// Sprite set used for each generation as (`sprites.versions` key, game key, label).
+pub const GENERATIONS: [(&str, &str, &str); 8] = [
+    ("generation-i", "red-blue", "Gen I"),
+    ("generation-ii", "crystal", "Gen II"),
+    ("generation-iii", "emerald", "Gen III"),
+    ("generation-iv", "platinum", "Gen IV"),
+    ("generation-v", "black-white", "Gen V"),
+    ("generation-vi", "x-y", "Gen VI"),
+    ("generation-vii", "ultra-sun-ultra-moon", "Gen VII"),
+    ("generation-viii", "icons", "Gen VIII"),
+];
+
+/// Parse a generation setting (`1`..`8`).
+pub fn parse_generation(s: &str) -> Option<u8> {
+    s.trim()
+        .parse::<u8>()
+        .ok()
+        .filter(|g| (1..=GENERATIONS.len() as u8).contains(g))
+}
+
+/// Pixels with alpha below this are treated as fully transparent when rendering.
+pub const ALPHA_THRESHOLD: u8 = 128;
+
+/// One RGBA sprite pixel.
+pub type Pixel = (u8, u8, u8, u8);
+
+/// Compact RGBA thumbnail stored in the in-memory cache.
+pub struct SpriteThumb {
+    pub w: u32,
+    pub h: u32,
+    /// RGBA pixels in row-major order (len = w*h*4)
+    pub pixels: Vec<u8>,
+}
+
+/// Which sprite of a Pokémon to show. The default value is the classic front sprite.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpriteVariant {
+    pub shiny: bool,
+    pub back: bool,
+    pub female: bool,
+    /// Official artwork ignores `back`, `female` and `generation` (PokeAPI only
+    /// has modern front art).
+    pub artwork: bool,
+    /// Generation-scoped sprite set (1..=8); `None` is the modern sprite.
+    pub generation: Option<u8>,
+}
+
+impl SpriteVariant {
+    /// Every variant downloaded by `--sprite-variants`, except the default sprite
+    /// which is always fetched.
+    pub fn downloadable() -> Vec<SpriteVariant> {
+        let mut out = Self::generation_set(None, true);
+        out.retain(|v| *v != SpriteVariant::default());
+        for shiny in [false, true] {
+            out.push(SpriteVariant {
+                shiny,
+                artwork: true,
+                ..Default::default()
+            });
+        }
+        out
+    }
+
+    /// Front/back, shiny and female combinations of one sprite set. Only the
+    /// default front sprite is included unless `with_variants` is set.
+    pub fn generation_set(generation: Option<u8>, with_variants: bool) -> Vec<SpriteVariant> {
+        let mut out = Vec::new();
+        for back in [false, true] {
+            for shiny in [false, true] {
+                for female in [false, true] {
+                    let v = SpriteVariant {
+                        shiny,
+                        back,
+                        female,
+                        artwork: false,
+                        generation,
+                    };
+                    if with_variants || !(back || shiny || female) {
+                        out.push(v);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// PokeAPI sprite key, also used as the variant's directory name.
+    pub fn key(&self) -> String {
+        if self.artwork {
+            return if self.shiny {
+                "official-artwork-shiny".to_string()
+            } else {
+                "official-artwork".to_string()
+            };
+        }
+        format!(
+            "{}_{}{}",
+            if self.back { "back" } else { "front" },
+            if self.shiny { "shiny" } else { "default" },
+            if self.female { "_female" } else { "" }
+        )
+    }
+
+    /// Short label for the sprite panel title, e.g. `Shiny Back ♀`.
+    pub fn label(&self) -> String {
+        let mut parts: Vec<&str> = Vec::new();
+        if let Some(g) = self.generation.filter(|_| !self.artwork) {
+            parts.push(GENERATIONS[g as usize - 1].2);
+        }
+        if self.shiny {
+            parts.push("Shiny");
+        }
+        if self.artwork {
+            parts.push("Artwork");
+        } else {
+            if self.back {
+                parts.push("Back");
+            }
+            if self.female {
+                parts.push("♀");
+            }
+        }
+        parts.join(" ")
+    }
+
+    /// Location of this variant's PNG for Pokémon `id`.
+    pub fn path(&self, id: u32) -> PathBuf {
+        let root = match self.generation {
+            Some(g) if !self.artwork => format!("{}/gen-{}", SPRITE_DIR, g),
+            _ => SPRITE_DIR.to_string(),
+        };
+        if self.shiny || self.back || self.female || self.artwork {
+            PathBuf::from(format!("{}/{}/{}.png", root, self.key(), id))
+        } else {
+            PathBuf::from(format!("{}/{}.png", root, id))
+        }
+    }
+
+    /// URL of this variant in a `/pokemon/{id}` response, if PokeAPI has one.
+    pub fn url<'a>(&self, p_json: &'a serde_json::Value) -> Option<&'a str> {
+        let sprites = p_json.get("sprites")?;
+        let url = if self.artwork {
+            sprites
+                .get("other")?
+                .get("official-artwork")?
+                .get(if self.shiny {
+                    "front_shiny"
+                } else {
+                    "front_default"
+                })?
+        } else if let Some(g) = self.generation {
+            let (gen_key, game_key, _) = GENERATIONS.get(g as usize - 1)?;
+            sprites
+                .get("versions")?
+                .get(gen_key)?
+                .get(game_key)?
+                .get(self.key())?
+        } else {
+            sprites.get(self.key())?
+        };
+        url.as_str()
+    }
+
+    /// Variants to try in order when this one is missing on disk: drop `female`
+    /// first (most Pokémon have no gender differences), then `back`, then `shiny`.
+    /// A generation-scoped sprite falls back to the same chain in the modern set,
+    /// ending at the default `front_default` sprite.
+    fn fallbacks(&self) -> Vec<SpriteVariant> {
+        let mut start = *self;
+        if start.artwork {
+            start.generation = None;
+        }
+        let mut chains = vec![start];
+        if start.generation.is_some() {
+            chains.push(SpriteVariant {
+                generation: None,
+                ..start
+            });
+        }
+        let mut out = Vec::new();
+        for mut v in chains {
+            out.push(v);
+            for step in 0..4 {
+                match step {
+                    0 => v.female = false,
+                    1 => v.back = false,
+                    2 => v.shiny = false,
+                    _ => v.artwork = false,
+                }
+                if !out.contains(&v) {
+                    out.push(v);
+                }
+            }
+        }
+        out
+    }
+
+    /// The closest variant to this one that exists on disk for `id`.
+    pub fn resolve(&self, id: u32) -> Option<(SpriteVariant, PathBuf)> {
+        self.fallbacks()
+            .into_iter()
+            .map(|v| (v, v.path(id)))
+            .find(|(_, path)| path.exists())
+    }
+}
+
+/// Bounding box `(x, y, w, h)` of the visible pixels in `img`, or `None` if it
+/// is fully transparent.
+fn content_bounds(img: &image::RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, p) in img.enumerate_pixels() {
+        if p[3] >= ALPHA_THRESHOLD {
+            x0 = x0.min(x);
+            y0 = y0.min(y);
+            x1 = x1.max(x);
+            y1 = y1.max(y);
+        }
+    }
+    if x0 > x1 || y0 > y1 {
+        return None;
+    }
+    Some((x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+}
+
+/// Cut `bounds` out of `img` and pad it to a centered square.
+fn crop_square(img: &image::RgbaImage, (x, y, w, h): (u32, u32, u32, u32)) -> image::RgbaImage {
+    let side = w.max(h);
+    let mut out = image::RgbaImage::new(side, side);
+    let cropped = image::imageops::crop_imm(img, x, y, w, h).to_image();
+    image::imageops::overlay(
+        &mut out,
+        &cropped,
+        ((side - w) / 2) as i64,
+        ((side - h) / 2) as i64,
+    );
+    out
+}
+
+/// Crop `img` to the bounding box of its visible pixels and pad the result to a
+/// centered square, so sprites with wide transparent margins fill the panel.
+/// Fully transparent images are returned unchanged.
+pub fn crop_to_content(img: &image::RgbaImage) -> image::RgbaImage {
+    match content_bounds(img) {
+        Some(bounds) => crop_square(img, bounds),
+        None => img.clone(),
+    }
+}
+
+/// Load the `w` x `h` RGBA thumbnail of the PNG at `path`: from `disk` when it
+/// has one for this file's contents and size, otherwise by decoding the PNG,
+/// cropping empty margins and resizing (and then storing the result in `disk`).
+pub fn load_thumb(
+    path: &std::path::Path,
+    w: u32,
+    h: u32,
+    disk: Option<&DiskCache>,
+) -> Option<SpriteThumb> {
+    let bytes = std::fs::read(path).ok()?;
+    let hash = sprite_hash(&bytes);
+    if let Some(pixels) = disk.and_then(|d| d.get(hash, w, h)) {
+        if pixels.len() == (w * h * 4) as usize {
+            return Some(SpriteThumb { w, h, pixels });
+        }
+    }
+    let img = image::load_from_memory(&bytes).ok()?;
+    let cropped = crop_to_content(&img.to_rgba8());
+    let pixels = image::imageops::resize(&cropped, w, h, FilterType::Lanczos3).into_raw();
+    if let Some(d) = disk {
+        d.put(hash, w, h, &pixels);
+    }
+    Some(SpriteThumb { w, h, pixels })
+}
+
+impl SpriteThumb {
+    /// Pixel rows sized `w` x `h`. An exact size match returns the thumbnail's
+    /// pixels directly; otherwise we perform an in-memory resize, which is much
+    /// cheaper than reloading full images from disk.
+    pub fn rows(&self, w: u32, h: u32) -> Option<Vec<Vec<Pixel>>> {
+        let to_rows = |raw: &[u8], w: u32| -> Vec<Vec<Pixel>> {
+            raw.chunks_exact(4)
+                .map(|p| (p[0], p[1], p[2], p[3]))
+                .collect::<Vec<Pixel>>()
+                .chunks(w as usize)
+                .map(|row| row.to_vec())
+                .collect()
+        };
+        if self.w == w && self.h == h {
+            return Some(to_rows(&self.pixels, w));
+        }
+        let buf = image::RgbaImage::from_raw(self.w, self.h, self.pixels.clone())?;
+        let resized = image::imageops::resize(&buf, w, h, FilterType::Lanczos3);
+        Some(to_rows(resized.as_raw(), w))
+    }
+
+    /// Heap bytes held by this thumbnail.
+    pub fn bytes(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+/// Where the animated front sprite (GIF) of Pokémon `id` is stored.
+pub fn animated_path(id: u32, shiny: bool) -> PathBuf {
+    if shiny {
+        PathBuf::from(format!("{}/animated/front_shiny/{}.gif", SPRITE_DIR, id))
+    } else {
+        PathBuf::from(format!("{}/animated/{}.gif", SPRITE_DIR, id))
+    }
+}
+
+/// URL of the animated front sprite in a `/pokemon/{id}` response. The Gen V
+/// Black/White animations are preferred; Showdown GIFs cover later Pokémon.
+pub fn animated_url(p_json: &serde_json::Value, shiny: bool) -> Option<&str> {
+    let key = if shiny {
+        "front_shiny"
+    } else {
+        "front_default"
+    };
+    let sprites = p_json.get("sprites")?;
+    sprites
+        .get("versions")
+        .and_then(|v| v.get("generation-v"))
+        .and_then(|g| g.get("black-white"))
+        .and_then(|bw| bw.get("animated"))
+        .and_then(|a| a.get(key))
+        .and_then(|u| u.as_str())
+        .or_else(|| sprites.get("other")?.get("showdown")?.get(key)?.as_str())
+}
+
+/// Decoded frames of an animated sprite, as thumbnails sharing one crop so the
+/// Pokémon does not jitter between frames.
+pub struct Animation {
+    pub frames: Vec<SpriteThumb>,
+    pub delays: Vec<std::time::Duration>,
+}
+
+impl Animation {
+    pub fn bytes(&self) -> usize {
+        self.frames.iter().map(|f| f.bytes()).sum()
+    }
+}
+
+/// Decode the GIF at `path` into an `Animation` with `w` x `h` frames, so the
+/// UI can draw them without resizing.
+pub fn load_animation(path: &std::path::Path, w: u32, h: u32) -> Option<Animation> {
+    use image::AnimationDecoder;
+    let file = std::io::BufReader::new(std::fs::File::open(path).ok()?);
+    let frames = image::codecs::gif::GifDecoder::new(file)
+        .ok()?
+        .into_frames()
+        .collect_frames()
+        .ok()?;
+    if frames.is_empty() {
+        return None;
+    }
+    let bounds = frames
+        .iter()
+        .filter_map(|f| content_bounds(f.buffer()))
+        .reduce(|(ax, ay, aw, ah), (bx, by, bw, bh)| {
+            let (x0, y0) = (ax.min(bx), ay.min(by));
+            let (x1, y1) = ((ax + aw).max(bx + bw), (ay + ah).max(by + bh));
+            (x0, y0, x1 - x0, y1 - y0)
+        })?;
+    let delays = frames
+        .iter()
+        .map(|f| {
+            let (num, den) = f.delay().numer_denom_ms();
+            // Browsers treat very short GIF delays as 100 ms; do the same.
+            let ms = num.checked_div(den).unwrap_or(0);
+            std::time::Duration::from_millis(if ms < 20 { 100 } else { ms as u64 })
+        })
+        .collect();
+    let frames = frames
+        .iter()
+        .map(|f| {
+            let img = crop_square(f.buffer(), bounds);
+            SpriteThumb {
+                w,
+                h,
+                pixels: image::imageops::resize(&img, w, h, FilterType::Lanczos3).into_raw(),
+            }
+        })
+        .collect();
+    Some(Animation { frames, delays })
+}
+
+/// Key of a decoded animation: Pokédex id, shiny, and the frame size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AnimationKey {
+    pub id: u32,
+    pub shiny: bool,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Decoded animations by `AnimationKey`. Entries are evicted in insertion
+/// order (oldest first, regardless of use) once their frames exceed `budget`
+/// bytes; an animation larger than the whole budget is not kept at all.
+pub struct FrameCache {
+    budget: usize,
+    used: usize,
+    order: std::collections::VecDeque<AnimationKey>,
+    entries: std::collections::HashMap<AnimationKey, Option<Animation>>,
+}
+
+impl FrameCache {
+    pub fn new(budget: usize) -> Self {
+        FrameCache {
+            budget,
+            used: 0,
+            order: std::collections::VecDeque::new(),
+            entries: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Bytes of decoded frames currently held.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// The animation for `key`: `None` if it has not been decoded yet,
+    /// `Some(None)` if the Pokémon has no animated sprite on disk.
+    pub fn get(&self, key: &AnimationKey) -> Option<Option<&Animation>> {
+        self.entries.get(key).map(|a| a.as_ref())
+    }
+
+    /// Drop every entry, including the remembered misses, so animations
+    /// downloaded since are decoded on the next request.
+    pub fn clear(&mut self) {
+        self.used = 0;
+        self.order.clear();
+        self.entries.clear();
+    }
+
+    /// Store a decoded animation (or its absence, so the disk is only checked
+    /// once), evicting the oldest entries to stay within the budget. An
+    /// animation that cannot fit is stored as absent, so the static sprite is
+    /// shown instead.
+    pub fn insert(&mut self, key: AnimationKey, anim: Option<Animation>) {
+        if self.entries.contains_key(&key) {
+            return;
+        }
+        let anim = anim.filter(|a| a.bytes() <= self.budget);
+        let size = anim.as_ref().map_or(0, |a| a.bytes());
+        while self.used + size > self.budget {
+            match self.order.pop_front() {
+                Some(old) => {
+                    if let Some(Some(a)) = self.entries.remove(&old) {
+                        self.used -= a.bytes();
+                    }
+                }
+                None => break,
+            }
+        }
+        self.used += size;
+        self.order.push_back(key);
+        self.entries.insert(key, anim);
+    }
+}
+
+/// Key of a cached thumbnail: Pokédex id, the variant it shows and its size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ThumbKey {
+    pub id: u32,
+    pub variant: SpriteVariant,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// Hit/miss counters and memory use of a `ThumbCache`, for the debug overlay.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Least-recently-used thumbnail cache bounded by a byte budget.
+pub struct ThumbCache {
+    budget: usize,
+    used: usize,
+    /// Monotonic counter stamped on each access; the smallest stamp is evicted.
+    clock: u64,
+    entries: std::collections::HashMap<ThumbKey, (SpriteThumb, u64)>,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl ThumbCache {
+    pub fn new(budget: usize) -> Self {
+        ThumbCache {
+            budget,
+            used: 0,
+            clock: 0,
+            entries: std::collections::HashMap::new(),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    /// Look up `key`, marking it most recently used and counting a hit or miss.
+    pub fn get(&mut self, key: &ThumbKey) -> Option<&SpriteThumb> {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.1 = self.clock;
+                Some(&entry.0)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Whether `key` is cached, without touching recency or counters.
+    pub fn contains(&self, key: &ThumbKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Insert `thumb`, evicting least recently used entries to stay in budget.
+    pub fn insert(&mut self, key: ThumbKey, thumb: SpriteThumb) {
+        if let Some((old, _)) = self.entries.remove(&key) {
+            self.used -= old.bytes();
+        }
+        let size = thumb.bytes();
+        while self.used + size > self.budget {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, stamp))| *stamp)
+                .map(|(k, _)| *k);
+            match oldest.and_then(|k| self.entries.remove(&k)) {
+                Some((old, _)) => {
+                    self.used -= old.bytes();
+                    self.evictions += 1;
+                }
+                None => break,
+            }
+        }
+        self.clock += 1;
+        self.used += size;
+        self.entries.insert(key, (thumb, self.clock));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            bytes: self.used,
+            budget: self.budget,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An animation of `frames` frames of `side` x `side` pixels.
+    fn animation(frames: usize, side: u32) -> Animation {
+        Animation {
+            frames: (0..frames)
+                .map(|_| SpriteThumb {
+                    w: side,
+                    h: side,
+                    pixels: vec![0; (side * side * 4) as usize],
+                })
+                .collect(),
+            delays: vec![std::time::Duration::from_millis(100); frames],
+        }
+    }
+
+    fn key(id: u32) -> AnimationKey {
+        AnimationKey {
+            id,
+            shiny: false,
+            w: 8,
+            h: 8,
+        }
+    }
+
+    #[test]
+    fn frame_cache_stays_within_budget() {
+        // Each 8x8 frame is 256 bytes.
+        let mut cache = FrameCache::new(2048);
+        for (id, frames) in [(1, 4), (2, 3), (3, 2), (4, 8), (5, 1), (6, 20), (7, 5)] {
+            cache.insert(key(id), Some(animation(frames, 8)));
+            assert!(cache.used() <= 2048, "{} bytes after #{}", cache.used(), id);
+            let held: usize = (1..=id)
+                .filter_map(|i| cache.get(&key(i)).flatten())
+                .map(Animation::bytes)
+                .sum();
+            assert_eq!(cache.used(), held);
+        }
+    }
+
+    #[test]
+    fn frame_cache_evicts_oldest_first() {
+        let mut cache = FrameCache::new(2048);
+        cache.insert(key(1), Some(animation(4, 8)));
+        cache.insert(key(2), Some(animation(3, 8)));
+        cache.insert(key(3), None);
+        cache.insert(key(4), Some(animation(2, 8)));
+        assert_eq!(cache.used(), 5 * 256);
+        assert!(cache.get(&key(1)).is_none());
+        assert!(cache.get(&key(2)).flatten().is_some());
+        assert_eq!(cache.get(&key(3)).map(|a| a.is_none()), Some(true));
+    }
+
+    #[test]
+    fn frame_cache_drops_oversized_animations() {
+        let mut cache = FrameCache::new(2048);
+        cache.insert(key(1), Some(animation(4, 8)));
+        cache.insert(key(2), Some(animation(9, 8)));
+        // Remembered as absent, and nothing else had to go.
+        assert_eq!(cache.get(&key(2)).map(|a| a.is_none()), Some(true));
+        assert!(cache.get(&key(1)).flatten().is_some());
+        assert_eq!(cache.used(), 4 * 256);
+    }
+
+    fn thumb(side: u32) -> SpriteThumb {
+        SpriteThumb {
+            w: side,
+            h: side,
+            pixels: vec![0; (side * side * 4) as usize],
+        }
+    }
+
+    fn thumb_key(id: u32) -> ThumbKey {
+        ThumbKey {
+            id,
+            variant: SpriteVariant::default(),
+            w: 8,
+            h: 8,
+        }
+    }
+
+    #[test]
+    fn thumb_cache_evicts_least_recently_used() {
+        // Room for three 8x8 thumbnails.
+        let mut cache = ThumbCache::new(3 * 256);
+        for id in 1..=3 {
+            cache.insert(thumb_key(id), thumb(8));
+        }
+        assert!(cache.get(&thumb_key(1)).is_some());
+        cache.insert(thumb_key(4), thumb(8));
+        // #2 was inserted after #1 but used less recently.
+        assert!(cache.contains(&thumb_key(1)));
+        assert!(!cache.contains(&thumb_key(2)));
+        assert!(cache.contains(&thumb_key(3)));
+        assert!(cache.contains(&thumb_key(4)));
+
+        // `contains` does not count as a use.
+        assert!(cache.contains(&thumb_key(3)));
+        cache.insert(thumb_key(5), thumb(8));
+        assert!(!cache.contains(&thumb_key(3)));
+        assert!(cache.contains(&thumb_key(1)));
+    }
+
+    #[test]
+    fn thumb_cache_accounting() {
+        let mut cache = ThumbCache::new(1000);
+        cache.insert(thumb_key(1), thumb(8));
+        cache.insert(thumb_key(2), thumb(4));
+        assert_eq!(cache.stats().bytes, 256 + 64);
+        // Replacing an entry frees the old thumbnail.
+        cache.insert(thumb_key(1), thumb(10));
+        assert_eq!(cache.stats().bytes, 400 + 64);
+        // 14x14 is 784 bytes: both others have to go.
+        cache.insert(thumb_key(3), thumb(14));
+        assert!(cache.get(&thumb_key(2)).is_none());
+        assert!(cache.get(&thumb_key(3)).is_some());
+        assert!(cache.get(&thumb_key(3)).is_some());
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 784);
+        assert_eq!(stats.budget, 1000);
+        assert_eq!(stats.evictions, 2);
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 1);
+    }
+}
@@ -0,0 +1,207 @@
+//! Statistics derived from the loaded Pokémon, computed once per data load
+//! rather than on every frame.
+
+use std::collections::HashMap;
+
+use crate::models::Pokemon;
+
+/// Summary of one stat (or the base stat total) across the loaded Pokémon.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StatSummary {
+    pub min: u32,
+    pub max: u32,
+    pub mean: f32,
+}
+
+impl StatSummary {
+    /// Summary of `values`, which must be sorted.
+    fn from_values(values: &[u32]) -> Option<Self> {
+        let min = *values.first()?;
+        let max = *values.last()?;
+        let mean = values.iter().sum::<u32>() as f32 / values.len() as f32;
+        Some(StatSummary { min, max, mean })
+    }
+}
+
+/// How stat bars are scaled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatScale {
+    /// One scale for every stat, so bars are comparable across stats.
+    #[default]
+    Global,
+    /// Each stat against its own maximum, so e.g. a high Speed fills the bar
+    /// even though Speed tops out lower than HP.
+    PerStat,
+}
+
+impl StatScale {
+    pub fn next(self) -> Self {
+        match self {
+            StatScale::Global => StatScale::PerStat,
+            StatScale::PerStat => StatScale::Global,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatScale::Global => "global scale",
+            StatScale::PerStat => "per-stat scale",
+        }
+    }
+}
+
+/// Per-stat summaries and the base stat total summary of a set of Pokémon.
+#[derive(Clone, Debug, Default)]
+pub struct StatsIndex {
+    stats: HashMap<String, StatSummary>,
+    /// Sorted base values of each stat, for percentile ranks.
+    values: HashMap<String, Vec<u32>>,
+    pub bst: Option<StatSummary>,
+    bst_values: Vec<u32>,
+    /// Highest base value of any stat.
+    max: u32,
+}
+
+impl StatsIndex {
+    pub fn build(pokemons: &[Pokemon]) -> Self {
+        let mut values: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut totals = Vec::new();
+        for p in pokemons.iter().filter(|p| !p.stats.is_empty()) {
+            for st in &p.stats {
+                values.entry(st.name.clone()).or_default().push(st.base);
+            }
+            totals.push(p.base_stat_total());
+        }
+        for v in values.values_mut() {
+            v.sort_unstable();
+        }
+        totals.sort_unstable();
+        let stats: HashMap<String, StatSummary> = values
+            .iter()
+            .filter_map(|(name, v)| Some((name.clone(), StatSummary::from_values(v)?)))
+            .collect();
+        let max = stats.values().map(|s| s.max).max().unwrap_or(0);
+        StatsIndex {
+            stats,
+            values,
+            bst: StatSummary::from_values(&totals),
+            bst_values: totals,
+            max,
+        }
+    }
+
+    pub fn stat(&self, name: &str) -> Option<&StatSummary> {
+        self.stats.get(name)
+    }
+
+    /// Percentile rank (0-100) of `value` among the loaded values of `stat`.
+    pub fn percentile(&self, stat: &str, value: u32) -> Option<f32> {
+        percentile_rank(self.values.get(stat)?, value)
+    }
+
+    /// Percentile rank (0-100) of a base stat total.
+    pub fn bst_percentile(&self, total: u32) -> Option<f32> {
+        percentile_rank(&self.bst_values, total)
+    }
+
+    /// Value a full-width bar of `stat` stands for under `scale`.
+    pub fn bar_max(&self, stat: &str, scale: StatScale) -> u32 {
+        let max = match scale {
+            StatScale::Global => self.max,
+            StatScale::PerStat => self.stat(stat).map_or(self.max, |s| s.max),
+        };
+        // Base stats cannot exceed 255; avoid dividing by zero on empty data.
+        max.clamp(1, 255)
+    }
+}
+
+/// Share of `sorted` below `value`, counting ties as half, in percent.
+fn percentile_rank(sorted: &[u32], value: u32) -> Option<f32> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let below = sorted.partition_point(|&v| v < value);
+    let equal = sorted[below..].partition_point(|&v| v == value);
+    Some((below as f32 + equal as f32 / 2.0) * 100.0 / sorted.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Stat;
+
+    fn pokemon(stats: &[(&str, u32)]) -> Pokemon {
+        Pokemon {
+            stats: stats
+                .iter()
+                .map(|&(name, base)| Stat {
+                    name: name.to_string(),
+                    base,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn percentile_counts_ties_as_half() {
+        let sorted = [10, 20, 20, 30];
+        assert_eq!(percentile_rank(&sorted, 5), Some(0.0));
+        assert_eq!(percentile_rank(&sorted, 10), Some(12.5));
+        assert_eq!(percentile_rank(&sorted, 20), Some(50.0));
+        assert_eq!(percentile_rank(&sorted, 25), Some(75.0));
+        assert_eq!(percentile_rank(&sorted, 30), Some(87.5));
+        assert_eq!(percentile_rank(&sorted, 31), Some(100.0));
+        assert_eq!(percentile_rank(&[50, 50, 50], 50), Some(50.0));
+    }
+
+    #[test]
+    fn percentile_of_nothing() {
+        assert_eq!(percentile_rank(&[], 100), None);
+        let index = StatsIndex::build(&[]);
+        assert_eq!(index.percentile("speed", 100), None);
+        assert_eq!(index.bst_percentile(500), None);
+        assert_eq!(index.bst, None);
+    }
+
+    #[test]
+    fn summaries() {
+        let index = StatsIndex::build(&[
+            pokemon(&[("hp", 45), ("speed", 45)]),
+            pokemon(&[("hp", 250), ("speed", 50)]),
+            pokemon(&[("hp", 80), ("speed", 130)]),
+            // Not fetched yet: ignored.
+            pokemon(&[]),
+        ]);
+        let speed = index.stat("speed").unwrap();
+        assert_eq!((speed.min, speed.max), (45, 130));
+        assert!((speed.mean - 75.0).abs() < 1e-4);
+        let bst = index.bst.unwrap();
+        assert_eq!((bst.min, bst.max), (90, 300));
+        assert_eq!(index.percentile("hp", 80), Some(50.0));
+        let top = index.bst_percentile(300).unwrap();
+        assert!((top - 250.0 / 3.0).abs() < 1e-3);
+        assert_eq!(index.stat("attack"), None);
+    }
+
+    #[test]
+    fn bar_max() {
+        let index = StatsIndex::build(&[
+            pokemon(&[("hp", 255), ("speed", 45)]),
+            pokemon(&[("hp", 80), ("speed", 130)]),
+        ]);
+        assert_eq!(index.bar_max("speed", StatScale::Global), 255);
+        assert_eq!(index.bar_max("speed", StatScale::PerStat), 130);
+        // Unknown stats fall back to the global maximum.
+        assert_eq!(index.bar_max("attack", StatScale::PerStat), 255);
+
+        let empty = StatsIndex::build(&[]);
+        assert_eq!(empty.bar_max("hp", StatScale::Global), 1);
+        assert_eq!(empty.bar_max("hp", StatScale::PerStat), 1);
+
+        let zero = StatsIndex::build(&[pokemon(&[("hp", 0)])]);
+        assert_eq!(zero.bar_max("hp", StatScale::PerStat), 1);
+        let modded = StatsIndex::build(&[pokemon(&[("hp", 300)])]);
+        assert_eq!(modded.bar_max("hp", StatScale::Global), 255);
+    }
+}
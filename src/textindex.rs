@@ -0,0 +1,287 @@
+//! Inverted index over the words of each Pokémon's description, ability names
+//! and move names, so a search for "lightning" finds every Pokémon whose flavor
+//! text mentions it. Words match by prefix ("thunder" finds "Thunderbolt").
+
+use crate::models::Pokemon;
+use crate::utils::format_name;
+use std::collections::BTreeMap;
+
+/// Shorter search words only match names, which keeps the list usable while
+/// the first letters are being typed.
+pub const MIN_TERM_LEN: usize = 3;
+
+/// Characters of description shown on each side of a match.
+const SNIPPET_CONTEXT: usize = 30;
+
+/// Lowercase words of `text`: runs of letters and digits.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+pub struct TextIndex {
+    /// Word to the sorted Pokédex ids of the Pokémon it appears for.
+    words: BTreeMap<String, Vec<u32>>,
+}
+
+impl TextIndex {
+    pub fn build(all: &[Pokemon]) -> Self {
+        let mut index: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        for p in all {
+            let moves = p.moves.iter().flatten();
+            let texts = std::iter::once(&p.description)
+                .chain(&p.abilities)
+                .chain(moves);
+            for word in texts.flat_map(|t| words(t)) {
+                let ids = index.entry(word).or_default();
+                if ids.last() != Some(&p.pokedex) {
+                    ids.push(p.pokedex);
+                }
+            }
+        }
+        for ids in index.values_mut() {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        TextIndex { words: index }
+    }
+
+    /// Sorted Pokédex ids with a word starting with `term` (lowercase). Empty
+    /// for terms shorter than `MIN_TERM_LEN`.
+    pub fn search(&self, term: &str) -> Vec<u32> {
+        if term.chars().count() < MIN_TERM_LEN {
+            return Vec::new();
+        }
+        let mut ids: Vec<u32> = self
+            .words
+            .range(term.to_string()..)
+            .take_while(|(word, _)| word.starts_with(term))
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+/// Where a search word was found for a Pokémon, for the details pane: the
+/// text around the match and the matched part itself.
+pub struct Snippet {
+    pub source: &'static str,
+    pub before: String,
+    pub hit: String,
+    pub after: String,
+}
+
+/// Snippets of `p`'s description, abilities and moves with a word starting
+/// with `term` (lowercase): the first description match with some context,
+/// and every matching ability and move.
+pub fn snippets(p: &Pokemon, term: &str) -> Vec<Snippet> {
+    if term.chars().count() < MIN_TERM_LEN {
+        return Vec::new();
+    }
+    let starts = |w: &str| words(w).any(|w| w.starts_with(term));
+    let mut out = Vec::new();
+    if let Some(snippet) = description_snippet(&p.description, term) {
+        out.push(snippet);
+    }
+    for a in p.abilities.iter().filter(|a| starts(a)) {
+        out.push(Snippet {
+            source: "Ability",
+            before: String::new(),
+            hit: format_name(a),
+            after: String::new(),
+        });
+    }
+    for m in p.moves.iter().flatten().filter(|m| starts(m)) {
+        out.push(Snippet {
+            source: "Move",
+            before: String::new(),
+            hit: format_name(m),
+            after: String::new(),
+        });
+    }
+    out
+}
+
+fn description_snippet(text: &str, term: &str) -> Option<Snippet> {
+    let chars: Vec<char> = text.chars().collect();
+    // Start of the first word beginning with `term`.
+    let start = (0..chars.len()).find(|&i| {
+        (i == 0 || !chars[i - 1].is_alphanumeric())
+            && chars[i..]
+                .iter()
+                .flat_map(|c| c.to_lowercase())
+                .take(term.chars().count())
+                .eq(term.chars())
+    })?;
+    let end = (start..chars.len())
+        .find(|&i| !chars[i].is_alphanumeric())
+        .unwrap_or(chars.len());
+    let from = start.saturating_sub(SNIPPET_CONTEXT);
+    let to = (end + SNIPPET_CONTEXT).min(chars.len());
+    let before: String = chars[from..start].iter().collect();
+    let after: String = chars[end..to].iter().collect();
+    Some(Snippet {
+        source: "Description",
+        before: if from > 0 {
+            format!("…{}", before.trim_start())
+        } else {
+            before
+        },
+        hit: chars[start..end].iter().collect(),
+        after: if to < chars.len() {
+            format!("{}…", after.trim_end())
+        } else {
+            after
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pokemon(pokedex: u32, description: &str, abilities: &[&str], moves: &[&str]) -> Pokemon {
+        Pokemon {
+            pokedex,
+            description: description.to_string(),
+            abilities: abilities.iter().map(|a| a.to_string()).collect(),
+            moves: Some(moves.iter().map(|m| m.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    fn index() -> TextIndex {
+        TextIndex::build(&[
+            pokemon(
+                25,
+                "When several of these Pokémon gather, their electricity could build and cause lightning storms.",
+                &["static", "lightning-rod"],
+                &["thunderbolt", "thunder-punch", "quick-attack"],
+            ),
+            pokemon(
+                6,
+                "It spits fire that is hot enough to melt boulders.",
+                &["blaze"],
+                &["flamethrower", "thunder-punch"],
+            ),
+            pokemon(
+                1,
+                "A strange seed was planted on its back at birth.",
+                &["overgrow"],
+                &["vine-whip"],
+            ),
+        ])
+    }
+
+    fn text(s: &Snippet) -> String {
+        format!("{}[{}]{}", s.before, s.hit, s.after)
+    }
+
+    #[test]
+    fn words_match_by_prefix() {
+        let index = index();
+        assert_eq!(index.search("thunderbolt"), vec![25]);
+        assert_eq!(index.search("thunder"), vec![6, 25]);
+        assert_eq!(index.search("lightning"), vec![25]);
+        assert_eq!(index.search("flame"), vec![6]);
+        assert_eq!(index.search("seed"), vec![1]);
+        // Only word starts match.
+        assert_eq!(index.search("bolt"), Vec::<u32>::new());
+        assert_eq!(index.search("surf"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn short_terms_match_nothing() {
+        let index = index();
+        assert_eq!(index.search("th"), Vec::<u32>::new());
+        assert_eq!(index.search("a"), Vec::<u32>::new());
+        assert_eq!(index.search(""), Vec::<u32>::new());
+        assert_eq!(index.search("thu"), vec![6, 25]);
+        assert!(snippets(&pokemon(25, "Thunder", &[], &[]), "th").is_empty());
+    }
+
+    #[test]
+    fn ids_are_listed_once() {
+        // "lightning" is in the description and an ability; "thunder" in two
+        // moves and "thunderbolt" shares its prefix.
+        let index = index();
+        assert_eq!(index.search("lightning"), vec![25]);
+        assert_eq!(index.search("thunder"), vec![6, 25]);
+        let twice = TextIndex::build(&[
+            pokemon(7, "Water water everywhere", &[], &["water-gun"]),
+            pokemon(7, "water", &[], &[]),
+        ]);
+        assert_eq!(twice.search("water"), vec![7]);
+    }
+
+    #[test]
+    fn snippets_of_abilities_and_moves() {
+        let p = pokemon(
+            25,
+            "",
+            &["static", "lightning-rod"],
+            &["thunderbolt", "thunder-punch"],
+        );
+        let found: Vec<_> = snippets(&p, "thunder")
+            .iter()
+            .map(|s| (s.source, text(s)))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Move", "[Thunderbolt]".to_string()),
+                ("Move", "[Thunder Punch]".to_string())
+            ]
+        );
+        let found: Vec<_> = snippets(&p, "rod").iter().map(text).collect();
+        assert_eq!(found, ["[Lightning Rod]"]);
+    }
+
+    #[test]
+    fn short_descriptions_are_not_cut() {
+        let s = description_snippet("It spits fire that is hot.", "fire").unwrap();
+        assert_eq!(s.source, "Description");
+        assert_eq!(text(&s), "It spits [fire] that is hot.");
+        let s = description_snippet("Fire!", "fire").unwrap();
+        assert_eq!(text(&s), "[Fire]!");
+        assert!(description_snippet("Campfire", "fire").is_none());
+    }
+
+    #[test]
+    fn long_descriptions_get_ellipses_where_cut() {
+        let long = "a".repeat(40);
+        let s = description_snippet(&format!("{} bolt {}", long, long), "bolt").unwrap();
+        assert!(s.before.starts_with('…'));
+        assert!(s.after.ends_with('…'));
+        assert_eq!(s.hit, "bolt");
+
+        // Cut after the match only.
+        let s = description_snippet(&format!("Its bolt {}", long), "bolt").unwrap();
+        assert_eq!(s.before, "Its ");
+        assert!(s.after.ends_with('…'));
+        assert!(!s.before.contains('…'));
+
+        // Exactly the context on each side is not cut.
+        let context = "b".repeat(SNIPPET_CONTEXT - 1);
+        let s = description_snippet(&format!("{} bolt {}", context, context), "bolt").unwrap();
+        assert!(!s.before.contains('…') && !s.after.contains('…'));
+    }
+
+    #[test]
+    fn multibyte_descriptions() {
+        let s = description_snippet("Flabébé floats on the wind with its flower.", "flab").unwrap();
+        assert_eq!(text(&s), "[Flabébé] floats on the wind with its f…");
+        let s =
+            description_snippet("Ça plane. Éclair après éclair, l’orage gronde", "éclair").unwrap();
+        assert_eq!(text(&s), "Ça plane. [Éclair] après éclair, l’orage gronde");
+        let wide = "ピカチュウ".repeat(10);
+        let s = description_snippet(&format!("{} volt {}", wide, wide), "volt").unwrap();
+        assert_eq!(s.before.chars().count(), SNIPPET_CONTEXT + 1);
+        assert!(s.before.starts_with('…'));
+        assert_eq!(s.hit, "volt");
+        assert_eq!(s.after.chars().count(), SNIPPET_CONTEXT + 1);
+    }
+}
@@ -0,0 +1,249 @@
+//! Type effectiveness chart (Generation VI onwards) and type badge colors.
+
+/// The 18 types, by PokeAPI name.
+pub const TYPES: [&str; 18] = [
+    "normal", "fire", "water", "electric", "grass", "ice", "fighting", "poison", "ground",
+    "flying", "psychic", "bug", "rock", "ghost", "dragon", "dark", "steel", "fairy",
+];
+
+/// Attacking type, defending type and damage multiplier of every matchup that
+/// is not neutral.
+const CHART: &[(&str, &str, f32)] = &[
+    ("normal", "rock", 0.5),
+    ("normal", "ghost", 0.0),
+    ("normal", "steel", 0.5),
+    ("fire", "fire", 0.5),
+    ("fire", "water", 0.5),
+    ("fire", "grass", 2.0),
+    ("fire", "ice", 2.0),
+    ("fire", "bug", 2.0),
+    ("fire", "rock", 0.5),
+    ("fire", "dragon", 0.5),
+    ("fire", "steel", 2.0),
+    ("water", "fire", 2.0),
+    ("water", "water", 0.5),
+    ("water", "grass", 0.5),
+    ("water", "ground", 2.0),
+    ("water", "rock", 2.0),
+    ("water", "dragon", 0.5),
+    ("electric", "water", 2.0),
+    ("electric", "electric", 0.5),
+    ("electric", "grass", 0.5),
+    ("electric", "ground", 0.0),
+    ("electric", "flying", 2.0),
+    ("electric", "dragon", 0.5),
+    ("grass", "fire", 0.5),
+    ("grass", "water", 2.0),
+    ("grass", "grass", 0.5),
+    ("grass", "poison", 0.5),
+    ("grass", "ground", 2.0),
+    ("grass", "flying", 0.5),
+    ("grass", "bug", 0.5),
+    ("grass", "rock", 2.0),
+    ("grass", "dragon", 0.5),
+    ("grass", "steel", 0.5),
+    ("ice", "fire", 0.5),
+    ("ice", "water", 0.5),
+    ("ice", "grass", 2.0),
+    ("ice", "ice", 0.5),
+    ("ice", "ground", 2.0),
+    ("ice", "flying", 2.0),
+    ("ice", "dragon", 2.0),
+    ("ice", "steel", 0.5),
+    ("fighting", "normal", 2.0),
+    ("fighting", "ice", 2.0),
+    ("fighting", "poison", 0.5),
+    ("fighting", "flying", 0.5),
+    ("fighting", "psychic", 0.5),
+    ("fighting", "bug", 0.5),
+    ("fighting", "rock", 2.0),
+    ("fighting", "ghost", 0.0),
+    ("fighting", "dark", 2.0),
+    ("fighting", "steel", 2.0),
+    ("fighting", "fairy", 0.5),
+    ("poison", "grass", 2.0),
+    ("poison", "poison", 0.5),
+    ("poison", "ground", 0.5),
+    ("poison", "rock", 0.5),
+    ("poison", "ghost", 0.5),
+    ("poison", "steel", 0.0),
+    ("poison", "fairy", 2.0),
+    ("ground", "fire", 2.0),
+    ("ground", "electric", 2.0),
+    ("ground", "grass", 0.5),
+    ("ground", "poison", 2.0),
+    ("ground", "flying", 0.0),
+    ("ground", "bug", 0.5),
+    ("ground", "rock", 2.0),
+    ("ground", "steel", 2.0),
+    ("flying", "electric", 0.5),
+    ("flying", "grass", 2.0),
+    ("flying", "fighting", 2.0),
+    ("flying", "bug", 2.0),
+    ("flying", "rock", 0.5),
+    ("flying", "steel", 0.5),
+    ("psychic", "fighting", 2.0),
+    ("psychic", "poison", 2.0),
+    ("psychic", "psychic", 0.5),
+    ("psychic", "dark", 0.0),
+    ("psychic", "steel", 0.5),
+    ("bug", "fire", 0.5),
+    ("bug", "grass", 2.0),
+    ("bug", "fighting", 0.5),
+    ("bug", "poison", 0.5),
+    ("bug", "flying", 0.5),
+    ("bug", "psychic", 2.0),
+    ("bug", "ghost", 0.5),
+    ("bug", "dark", 2.0),
+    ("bug", "steel", 0.5),
+    ("bug", "fairy", 0.5),
+    ("rock", "fire", 2.0),
+    ("rock", "ice", 2.0),
+    ("rock", "fighting", 0.5),
+    ("rock", "ground", 0.5),
+    ("rock", "flying", 2.0),
+    ("rock", "bug", 2.0),
+    ("rock", "steel", 0.5),
+    ("ghost", "normal", 0.0),
+    ("ghost", "psychic", 2.0),
+    ("ghost", "ghost", 2.0),
+    ("ghost", "dark", 0.5),
+    ("dragon", "dragon", 2.0),
+    ("dragon", "steel", 0.5),
+    ("dragon", "fairy", 0.0),
+    ("dark", "fighting", 0.5),
+    ("dark", "psychic", 2.0),
+    ("dark", "ghost", 2.0),
+    ("dark", "dark", 0.5),
+    ("dark", "fairy", 0.5),
+    ("steel", "fire", 0.5),
+    ("steel", "water", 0.5),
+    ("steel", "electric", 0.5),
+    ("steel", "ice", 2.0),
+    ("steel", "rock", 2.0),
+    ("steel", "steel", 0.5),
+    ("steel", "fairy", 2.0),
+    ("fairy", "fire", 0.5),
+    ("fairy", "fighting", 2.0),
+    ("fairy", "poison", 0.5),
+    ("fairy", "dragon", 2.0),
+    ("fairy", "dark", 2.0),
+    ("fairy", "steel", 0.5),
+];
+
+/// Damage multiplier of an `attacking` move against a single `defending` type.
+pub fn effectiveness(attacking: &str, defending: &str) -> f32 {
+    CHART
+        .iter()
+        .find(|(a, d, _)| *a == attacking && *d == defending)
+        .map_or(1.0, |(_, _, m)| *m)
+}
+
+/// Multiplier of every attacking type against a Pokémon of `types`, skipping
+/// neutral ones.
+pub fn defensive_matchups(types: &[String]) -> Vec<(&'static str, f32)> {
+    TYPES
+        .iter()
+        .map(|&attacking| {
+            let m = types
+                .iter()
+                .map(|t| effectiveness(attacking, &t.to_lowercase()))
+                .product();
+            (attacking, m)
+        })
+        .filter(|&(_, m)| m != 1.0)
+        .collect()
+}
+
+/// Badge color of a type.
+pub fn type_rgb(t: &str) -> (u8, u8, u8) {
+    match t.to_lowercase().as_str() {
+        "normal" => (168, 168, 120),
+        "fire" => (240, 128, 48),
+        "water" => (104, 144, 240),
+        "grass" => (120, 200, 80),
+        "electric" => (248, 208, 48),
+        "ice" => (152, 216, 216),
+        "fighting" => (192, 48, 40),
+        "poison" => (160, 64, 160),
+        "ground" => (224, 192, 104),
+        "flying" => (168, 144, 240),
+        "psychic" => (248, 88, 136),
+        "bug" => (168, 184, 32),
+        "rock" => (184, 160, 56),
+        "ghost" => (112, 88, 152),
+        "dragon" => (112, 56, 248),
+        "dark" => (112, 88, 72),
+        "steel" => (184, 184, 208),
+        "fairy" => (238, 153, 172),
+        _ => (200, 200, 200),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matchups(types: &[&str]) -> Vec<(&'static str, f32)> {
+        let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+        defensive_matchups(&types)
+    }
+
+    fn multiplier(types: &[&str], attacking: &str) -> f32 {
+        matchups(types)
+            .into_iter()
+            .find(|(t, _)| *t == attacking)
+            .map_or(1.0, |(_, m)| m)
+    }
+
+    #[test]
+    fn charizard() {
+        assert_eq!(
+            matchups(&["fire", "flying"]),
+            vec![
+                ("fire", 0.5),
+                ("water", 2.0),
+                ("electric", 2.0),
+                ("grass", 0.25),
+                ("fighting", 0.5),
+                ("ground", 0.0),
+                ("bug", 0.25),
+                ("rock", 4.0),
+                ("steel", 0.5),
+                ("fairy", 0.5),
+            ]
+        );
+    }
+
+    #[test]
+    fn immunities() {
+        let gengar = ["ghost", "poison"];
+        assert_eq!(multiplier(&gengar, "normal"), 0.0);
+        assert_eq!(multiplier(&gengar, "fighting"), 0.0);
+        assert_eq!(multiplier(&gengar, "ghost"), 2.0);
+        assert_eq!(multiplier(&gengar, "ground"), 2.0);
+        assert_eq!(multiplier(&gengar, "bug"), 0.25);
+
+        let magearna = ["steel", "fairy"];
+        assert_eq!(multiplier(&magearna, "dragon"), 0.0);
+        assert_eq!(multiplier(&magearna, "poison"), 0.0);
+        assert_eq!(multiplier(&magearna, "fire"), 2.0);
+        assert_eq!(multiplier(&magearna, "bug"), 0.25);
+    }
+
+    #[test]
+    fn neutral_matchups_are_skipped() {
+        // Ice against Charizard: 0.5 from Fire, 2 from Flying.
+        assert_eq!(multiplier(&["fire", "flying"], "ice"), 1.0);
+        assert_eq!(
+            matchups(&["normal"]),
+            vec![("fighting", 2.0), ("ghost", 0.0)]
+        );
+        assert!(matchups(&[]).is_empty());
+    }
+
+    #[test]
+    fn type_names_are_case_insensitive() {
+        assert_eq!(matchups(&["Fire", "FLYING"]), matchups(&["fire", "flying"]));
+    }
+}
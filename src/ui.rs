@@ -1,27 +1,237 @@
+use crate::calc::{self, stat_range, CalcField, StatBuild, NATURES, STAT_NAMES};
+use crate::diskcache::DiskCache;
 use crate::fetch::FetchState;
-use crate::models::Pokemon;
-use crate::utils::{format_name, text_to_lines};
+use crate::graphics::{self, GraphicRequest, GraphicsProtocol};
+use crate::history;
+use crate::loader::{GraphicCache, LoadRequest, SpriteLoader};
+use crate::models::{Pokemon, DEXES};
+use crate::presets::{self, Preset};
+use crate::query::Query;
+use crate::radar::{self, RadarSeries};
+use crate::render::{self, Palette, RenderMode, SpriteBackground};
+use crate::sprites::{AnimationKey, FrameCache, Pixel, SpriteVariant, ThumbCache, ThumbKey};
+use crate::stats::{StatScale, StatsIndex};
+use crate::textindex::{self, TextIndex};
+use crate::types::{defensive_matchups, type_rgb};
+use crate::utils::{format_location, format_name, text_to_lines, version_rank};
 use ratatui::backend::CrosstermBackend;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Span, Spans};
+use ratatui::widgets::canvas::{Canvas, Context};
 use ratatui::widgets::Gauge;
-use ratatui::widgets::{
-    Block, Borders, List, ListItem, Paragraph, Wrap,
-};
-use std::collections::HashMap;
-use image::imageops::FilterType;
-use ratatui::Terminal;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Tabs, Wrap};
+use ratatui::{Frame, Terminal};
 use std::io;
 use std::io::Stdout;
 use std::sync::{Arc, Mutex};
 
-/// Compact RGB thumbnail stored in the in-memory cache.
-pub struct SpriteThumb {
-    pub w: u32,
-    pub h: u32,
-    /// RGB pixels in row-major order (len = w*h*3)
-    pub pixels: Vec<u8>,
+/// Default byte budget of the thumbnail cache (8 MiB is ~900 48x48 thumbnails).
+pub const DEFAULT_CACHE_BUDGET: usize = 8 * 1024 * 1024;
+
+/// How many list entries on each side of the selection are prefetched.
+const PREFETCH_RADIUS: usize = 8;
+
+/// Rendered sprites kept by `App::sprite_spans`; enough for every frame of a
+/// typical animation plus the compare view.
+const SPRITE_MEMO_ENTRIES: usize = 128;
+
+/// Frames of longer animations are not memoized: cycling through more frames
+/// than the memo holds would evict each one before it is shown again.
+const MAX_MEMO_FRAMES: usize = SPRITE_MEMO_ENTRIES / 2;
+
+/// Everything that determines how a sprite is rendered.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct SpriteMemoKey {
+    id: u32,
+    variant: SpriteVariant,
+    w: u32,
+    h: u32,
+    mode: RenderMode,
+    bg: SpriteBackground,
+    palette: Palette,
+    frame: Option<usize>,
+}
+
+/// Memory budget for decoded animation frames (16 MiB is roughly 180 animations).
+const ANIMATION_BUDGET: usize = 16 * 1024 * 1024;
+
+/// Most Pokémon shown side by side in the compare view.
+const MAX_COMPARED: usize = 4;
+
+/// Full-text matches listed above the description before the rest are counted.
+const MAX_SNIPPETS: usize = 6;
+
+/// Key / action pairs listed in the help modal.
+const KEYBINDINGS: &[(&str, &str)] = &[
+    ("q", "Quit"),
+    ("/", "Enter search mode"),
+    ("Enter/Esc", "Finish or cancel search mode"),
+    ("Up/Down", "Navigate list; in search: recall history"),
+    ("Ctrl-R", "In search: reverse search through history"),
+    ("Ctrl-U", "In search: clear the search"),
+    ("r", "Refresh fetch (background)"),
+    ("Tab", "Switch detail tab"),
+    ("v", "Filter encounters by version"),
+    ("d", "Switch Pokédex (National, Kanto, ...)"),
+    ("s / b / f", "Toggle shiny / back / female sprite"),
+    ("a", "Toggle official artwork"),
+    ("m", "Switch sprite render mode"),
+    ("p", "Pause / resume sprite animation"),
+    ("n", "Scale stat bars globally / per stat"),
+    ("c", "Edit the stat calculator (Esc to finish)"),
+    ("x / X", "Radar overlay: cycle / pin selection"),
+    ("Space", "Mark / unmark for comparison (up to 4)"),
+    ("C", "Toggle compare view of marked Pokémon"),
+    ("o / O", "Cycle sort field / flip sort direction"),
+    ("g", "Go to a dex number (Enter to jump)"),
+    ("P", "Pick, save or delete a saved search preset"),
+    ("PgUp/PgDn", "Scroll details"),
+    ("F12", "Toggle debug overlay"),
+    ("F1 / h", "Toggle this help modal"),
+];
+
+/// Tabs of the lower-right detail pane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DetailTab {
+    Description,
+    Encounters,
+    Calculator,
+    Radar,
+}
+
+impl DetailTab {
+    pub const ALL: [DetailTab; 4] = [
+        DetailTab::Description,
+        DetailTab::Encounters,
+        DetailTab::Calculator,
+        DetailTab::Radar,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            DetailTab::Description => "Description",
+            DetailTab::Encounters => "Where to find",
+            DetailTab::Calculator => "Stat calculator",
+            DetailTab::Radar => "Radar",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|t| *t == self).unwrap_or(0)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Field the Pokémon list is ordered by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// Number in the selected Pokédex.
+    Number,
+    Name,
+    /// Base stat at this index of `calc::STAT_NAMES`.
+    Stat(usize),
+    Bst,
+    Height,
+    Weight,
+    BaseExperience,
+    Type,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 13] = [
+        SortKey::Number,
+        SortKey::Name,
+        SortKey::Stat(0),
+        SortKey::Stat(1),
+        SortKey::Stat(2),
+        SortKey::Stat(3),
+        SortKey::Stat(4),
+        SortKey::Stat(5),
+        SortKey::Bst,
+        SortKey::Height,
+        SortKey::Weight,
+        SortKey::BaseExperience,
+        SortKey::Type,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            SortKey::Number => "No.".to_string(),
+            SortKey::Name => "name".to_string(),
+            SortKey::Stat(i) => stat_label(STAT_NAMES[i]),
+            SortKey::Bst => "BST".to_string(),
+            SortKey::Height => "height".to_string(),
+            SortKey::Weight => "weight".to_string(),
+            SortKey::BaseExperience => "base EXP".to_string(),
+            SortKey::Type => "type".to_string(),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|k| *k == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Order of `a` and `b` by this field (ascending), falling back to their
+    /// number in `dex` so equal values keep a stable, meaningful order.
+    fn compare(self, a: &Pokemon, b: &Pokemon, dex: &str) -> std::cmp::Ordering {
+        let base = |p: &Pokemon, name: &str| {
+            p.stats
+                .iter()
+                .find(|s| s.name == name)
+                .map_or(0, |s| s.base)
+        };
+        let number = |p: &Pokemon| p.dex_number(dex).unwrap_or(u32::MAX);
+        let by_field = match self {
+            SortKey::Number => number(a).cmp(&number(b)),
+            SortKey::Name => a.name.cmp(&b.name),
+            SortKey::Stat(i) => base(a, STAT_NAMES[i]).cmp(&base(b, STAT_NAMES[i])),
+            SortKey::Bst => a.base_stat_total().cmp(&b.base_stat_total()),
+            SortKey::Height => a.height.cmp(&b.height),
+            SortKey::Weight => a.weight.cmp(&b.weight),
+            SortKey::BaseExperience => a.base_experience.cmp(&b.base_experience),
+            SortKey::Type => a.types.cmp(&b.types),
+        };
+        by_field.then_with(|| number(a).cmp(&number(b)))
+    }
+}
+
+/// Second polygon drawn on the radar chart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RadarOverlay {
+    #[default]
+    Off,
+    DexAverage,
+    /// The Pokémon pinned with `X`.
+    Pinned,
+}
+
+/// Ctrl-R search through the search history.
+pub struct ReverseSearch {
+    pub pattern: String,
+    /// Index into `App::search_history` of the entry shown, if any matches.
+    pub found: Option<usize>,
+    /// Search box text before the reverse search, restored on cancel.
+    draft: String,
+}
+
+/// State of the preset picker popup.
+#[derive(Default)]
+pub struct PresetPicker {
+    /// Highlighted row: 0 is "no preset", then `App::presets` in order.
+    pub selected: usize,
+    /// Name being typed to save the live search as a preset.
+    pub naming: Option<String>,
+    /// Last problem (a preset that doesn't parse, a failed save).
+    pub message: Option<String>,
 }
 
 pub struct App {
@@ -33,16 +243,105 @@ pub struct App {
     pub fetch_state: Option<Arc<Mutex<FetchState>>>,
     pub show_sprites: bool,
     pub show_help: bool,
-    // in-memory cache of compact resized sprite thumbnails (RGB bytes).
-    // Use an Arc<Mutex<...>> so a background thread can populate the cache
+    pub help_scroll: u16,
+    // in-memory LRU cache of compact resized sprite thumbnails (RGBA bytes),
+    // keyed by Pokédex id and the sprite variant the thumbnail was made from.
+    // Use an Arc<Mutex<...>> so the loader thread can populate the cache
     // without blocking the UI thread.
-    pub sprite_cache: std::sync::Arc<std::sync::Mutex<HashMap<u32, SpriteThumb>>>,
+    pub sprite_cache: std::sync::Arc<std::sync::Mutex<ThumbCache>>,
+    /// Decodes sprites in the background; see `loader`.
+    loader: Option<SpriteLoader>,
+    /// Set while drawing when a sprite is still being decoded, so the main
+    /// loop polls for the result instead of waiting for the next tick.
+    pub sprite_loading: bool,
+    /// Selection and variant of the last prefetch request, to avoid repeats.
+    last_prefetch: Option<ThumbKey>,
+    pub show_debug: bool,
+    /// Persistent thumbnail cache shared with the loader thread.
+    pub disk_cache: Option<std::sync::Arc<DiskCache>>,
+    /// Rendered sprite rows, see `sprite_spans`.
+    /// Entries are stamped with `sprite_memo_clock` on each use, and the least
+    /// recently used one is evicted when full.
+    sprite_memo: std::collections::HashMap<SpriteMemoKey, (Vec<Spans<'static>>, u64)>,
+    sprite_memo_clock: u64,
+    /// Pixel size the sprite panel last rendered at (for prefetching).
+    sprite_size: Option<(u32, u32)>,
+    /// Sprite variant shown in the sprite panel (toggled with s/b/f/a).
+    pub sprite_variant: SpriteVariant,
+    pub render_mode: RenderMode,
+    /// What transparent sprite pixels are drawn as.
+    pub sprite_bg: SpriteBackground,
+    /// Maps RGB sprite and badge colors onto the terminal's color depth.
+    pub palette: Palette,
+    /// Animated sprites play whenever they have been downloaded; `p` pauses.
+    pub anim_paused: bool,
+    pub anim_frame: usize,
+    anim_elapsed: std::time::Duration,
+    pub frame_cache: Arc<Mutex<FrameCache>>,
+    /// Graphics protocol supported by the terminal, if any.
+    pub graphics: Option<GraphicsProtocol>,
+    /// Graphic the current frame wants on screen, and the one last written.
+    pub graphic_request: Option<GraphicRequest>,
+    pub graphic_shown: Option<GraphicRequest>,
+    /// Encoded graphics sequences, filled by the loader thread.
+    graphic_cache: Arc<Mutex<GraphicCache>>,
+    pub detail_tab: DetailTab,
+    /// Restrict the "Where to find" tab to a single game version (`None` = all).
+    pub encounter_version: Option<String>,
+    /// Vertical scroll offset of the detail pane; reset when the selection changes.
+    pub detail_scroll: u16,
+    /// Index into `DEXES` of the Pokédex the list is numbered and ordered by.
+    pub dex: usize,
+    /// Stat summaries of `all_pokemons`, rebuilt whenever the data changes.
+    pub stats_index: StatsIndex,
+    pub stat_scale: StatScale,
+    /// Word index of descriptions, abilities and moves, rebuilt with the data.
+    pub text_index: TextIndex,
+    /// Level, nature, IVs and EVs applied in the calculator tab.
+    pub calc: StatBuild,
+    /// Whether keys edit the calculator, and the field being edited (an index
+    /// for `CalcField::from_index`).
+    pub calc_editing: bool,
+    calc_field: usize,
+    pub radar_overlay: RadarOverlay,
+    /// Pokédex id of the Pokémon pinned as radar overlay.
+    pub radar_pinned: Option<u32>,
+    /// Pokédex ids marked for the compare view, in marking order.
+    pub marked: Vec<u32>,
+    pub compare_mode: bool,
+    pub sort_key: SortKey,
+    pub sort_desc: bool,
+    /// Last query that parsed; the list stays filtered by it while the one
+    /// being typed has an error.
+    query: Option<Query>,
+    pub query_error: Option<String>,
+    /// Number being typed after `g`, while the go-to prompt is open, and why
+    /// the last one entered could not be jumped to.
+    pub goto_input: Option<String>,
+    pub goto_error: Option<String>,
+    /// Saved searches, and the one applied together with the live query
+    /// (its name and parsed query). While `presets.json` can't be loaded,
+    /// `presets_error` says why and presets aren't saved over it.
+    pub presets: Vec<Preset>,
+    pub presets_error: Option<String>,
+    pub preset: Option<(String, Query)>,
+    pub preset_picker: Option<PresetPicker>,
+    /// Past searches, oldest first, and the one recalled with Up/Down (with
+    /// the text typed before recalling, to return to).
+    pub search_history: Vec<String>,
+    /// Why the history couldn't be written, shown in the search box title.
+    pub history_error: Option<String>,
+    history_pos: Option<usize>,
+    history_draft: String,
+    pub reverse_search: Option<ReverseSearch>,
 }
 
 impl App {
     pub fn new(all: Vec<Pokemon>) -> Self {
         let visible = (0..all.len()).collect();
-        Self {
+        let stats_index = StatsIndex::build(&all);
+        let text_index = TextIndex::build(&all);
+        let mut app = Self {
             all_pokemons: all,
             visible,
             selected_visible: 0,
@@ -51,97 +350,393 @@ impl App {
             fetch_state: None,
             show_sprites: true,
             show_help: false,
-            sprite_cache: std::sync::Arc::new(std::sync::Mutex::new(HashMap::new())),
-        }
+            help_scroll: 0,
+            sprite_cache: std::sync::Arc::new(std::sync::Mutex::new(ThumbCache::new(
+                DEFAULT_CACHE_BUDGET,
+            ))),
+            loader: None,
+            sprite_loading: false,
+            last_prefetch: None,
+            show_debug: false,
+            disk_cache: None,
+            sprite_memo: std::collections::HashMap::new(),
+            sprite_memo_clock: 0,
+            sprite_size: None,
+            sprite_variant: SpriteVariant::default(),
+            render_mode: RenderMode::HalfBlock,
+            sprite_bg: SpriteBackground::Terminal,
+            palette: Palette::default(),
+            anim_paused: false,
+            anim_frame: 0,
+            anim_elapsed: std::time::Duration::ZERO,
+            frame_cache: Arc::new(Mutex::new(FrameCache::new(ANIMATION_BUDGET))),
+            graphics: None,
+            graphic_request: None,
+            graphic_shown: None,
+            graphic_cache: Arc::new(Mutex::new(GraphicCache::new())),
+            detail_tab: DetailTab::Description,
+            encounter_version: None,
+            detail_scroll: 0,
+            dex: 0,
+            stats_index,
+            text_index,
+            stat_scale: StatScale::default(),
+            calc: StatBuild::default(),
+            calc_editing: false,
+            calc_field: 0,
+            radar_overlay: RadarOverlay::default(),
+            radar_pinned: None,
+            marked: Vec::new(),
+            compare_mode: false,
+            sort_key: SortKey::Number,
+            sort_desc: false,
+            query: None,
+            goto_input: None,
+            goto_error: None,
+            presets: Vec::new(),
+            presets_error: None,
+            preset: None,
+            preset_picker: None,
+            search_history: Vec::new(),
+            history_pos: None,
+            history_draft: String::new(),
+            history_error: None,
+            reverse_search: None,
+            query_error: None,
+        };
+        app.apply_filter();
+        app
     }
 
-    /// Load (or generate) a compact thumbnail for `id` and return pixel rows sized `w` x `h`.
+    /// Pixel rows of the thumbnail for `id` sized `w` x `h`, if it is cached.
     ///
-    /// The cache stores a small thumbnail (e.g., 48x48 RGB). If the requested size
-    /// matches the cached thumb, we return its pixels directly. If the requested size
-    /// differs, we perform an in-memory resize from the cached thumbnail which is
-    /// much cheaper than reloading full images from disk.
-    pub fn get_sprite_pixels(&self, id: u32, w: u32, h: u32) -> Option<Vec<Vec<(u8, u8, u8)>>> {
-        const THUMB_W: u32 = 48;
-        const THUMB_H: u32 = 48;
-
-        // Attempt to get the thumbnail from cache; if missing, load and insert a thumb.
-        let mut cache = self.sprite_cache.lock().unwrap();
-        if !cache.contains_key(&id) {
-            let path = format!("data/sprites/{}.png", id);
-            if let Ok(img) = image::open(&path) {
-                // Resize to canonical thumbnail size and store RGB bytes.
-                let small = image::imageops::resize(&img.to_rgba8(), THUMB_W, THUMB_H, FilterType::Lanczos3);
-                let mut pixels = Vec::with_capacity((THUMB_W * THUMB_H * 3) as usize);
-                for y in 0..small.height() {
-                    for x in 0..small.width() {
-                        let p = small.get_pixel(x, y);
-                        pixels.push(p[0]);
-                        pixels.push(p[1]);
-                        pixels.push(p[2]);
-                    }
+    /// `variant` is resolved against the sprites on disk first, falling back to the
+    /// closest downloaded variant (e.g. female -> regular, shiny -> default).
+    /// Nothing is decoded here: on a miss the loader thread is asked for the
+    /// thumbnail and `sprite_loading` is set until it arrives.
+    pub fn get_sprite_pixels(
+        &mut self,
+        id: u32,
+        variant: SpriteVariant,
+        w: u32,
+        h: u32,
+    ) -> Option<Vec<Vec<Pixel>>> {
+        let (variant, _) = variant.resolve(id)?;
+        let key = ThumbKey { id, variant, w, h };
+
+        if let Some(thumb) = self.sprite_cache.lock().unwrap().get(&key) {
+            return thumb.rows(w, h);
+        }
+        let loader = self.loader.as_ref()?;
+        if !loader.failed(&key) {
+            loader.request(LoadRequest::Thumb(key));
+            self.sprite_loading = true;
+        }
+        None
+    }
+
+    /// Replace the thumbnail cache with one limited to `budget` bytes and start
+    /// the background loader thread that fills it and the other sprite caches.
+    pub fn start_loader(&mut self, budget: usize) {
+        self.sprite_cache = std::sync::Arc::new(std::sync::Mutex::new(ThumbCache::new(budget)));
+        self.loader = Some(SpriteLoader::spawn(
+            self.sprite_cache.clone(),
+            self.frame_cache.clone(),
+            self.graphic_cache.clone(),
+            self.disk_cache.clone(),
+        ));
+        self.last_prefetch = None;
+    }
+
+    /// Ask the loader thread to prefetch the sprites of the list entries around
+    /// the selection (nearest first) whenever the selection or variant changes.
+    pub fn prefetch_neighbors(&mut self) {
+        let loader = match &self.loader {
+            Some(loader) => loader,
+            None => return,
+        };
+        // The render size is only known once the sprite panel has been drawn.
+        let (w, h) = match self.sprite_size {
+            Some(size) => size,
+            None => return,
+        };
+        if self.visible.is_empty() {
+            return;
+        }
+        let sel = self.selected_visible;
+        let key = |id: u32| ThumbKey {
+            id,
+            variant: self.sprite_variant,
+            w,
+            h,
+        };
+        let current = key(self.all_pokemons[self.visible[sel]].pokedex);
+        if self.last_prefetch == Some(current) {
+            return;
+        }
+
+        let mut wanted = vec![current];
+        for d in 1..=PREFETCH_RADIUS {
+            for pos in [sel.checked_add(d), sel.checked_sub(d)]
+                .into_iter()
+                .flatten()
+            {
+                if let Some(&i) = self.visible.get(pos) {
+                    wanted.push(key(self.all_pokemons[i].pokedex));
                 }
-                cache.insert(
-                    id,
-                    SpriteThumb {
-                        w: THUMB_W,
-                        h: THUMB_H,
-                        pixels,
-                    },
-                );
-            } else {
-                return None;
             }
         }
+        loader.prefetch(wanted);
+        self.last_prefetch = Some(current);
+    }
 
-        // We have a thumbnail; if requested size equals thumb size, return directly.
-        if let Some(thumb) = cache.get(&id) {
-            // If exact match, iterate rows directly.
-            if thumb.w == w && thumb.h == h {
-                let mut rows: Vec<Vec<(u8, u8, u8)>> = Vec::with_capacity(h as usize);
-                for y in 0..h {
-                    let mut row = Vec::with_capacity(w as usize);
-                    let row_start = (y * w * 3) as usize;
-                    for x in 0..w {
-                        let idx = row_start + (x as usize) * 3;
-                        row.push((thumb.pixels[idx], thumb.pixels[idx + 1], thumb.pixels[idx + 2]));
-                    }
-                    rows.push(row);
+    /// Index of the animation frame to show and the number of frames, if
+    /// `variant` is a plain (optionally shiny) front sprite and its animation
+    /// has been decoded at `key`'s size. Until then the static sprite is shown
+    /// while the loader decodes the GIF.
+    fn animation_frame(
+        &mut self,
+        key: AnimationKey,
+        variant: SpriteVariant,
+    ) -> Option<(usize, usize)> {
+        if variant.back || variant.artwork || variant.generation.is_some() {
+            return None;
+        }
+        let len = match self.frame_cache.lock().unwrap().get(&key) {
+            Some(anim) => anim?.frames.len(),
+            None => {
+                if let Some(loader) = &self.loader {
+                    loader.request(LoadRequest::Animation(key));
+                    self.sprite_loading = true;
                 }
-                return Some(rows);
+                return None;
             }
+        };
+        Some((self.anim_frame % len, len))
+    }
 
-            // Otherwise, perform an in-memory resize from the thumbnail to the requested size.
-            // Build an ImageBuffer from the RGB bytes (add opaque alpha) and use image::resize.
-            let mut buf = image::RgbaImage::new(thumb.w, thumb.h);
-            for y in 0..thumb.h {
-                for x in 0..thumb.w {
-                    let idx = ((y * thumb.w + x) * 3) as usize;
-                    let r = thumb.pixels[idx];
-                    let g = thumb.pixels[idx + 1];
-                    let b = thumb.pixels[idx + 2];
-                    buf.put_pixel(x, y, image::Rgba([r, g, b, 255]));
-                }
+    /// Styled sprite rows for `id` at `w` x `h` pixels in render `mode` with the
+    /// current background and palette. Results are memoized per
+    /// (id, variant, size, mode, background, palette, animation frame), so
+    /// redrawing an unchanged sprite every tick only clones the cached rows. The
+    /// least recently used entry is evicted when the memo is full.
+    pub fn sprite_spans(
+        &mut self,
+        id: u32,
+        variant: SpriteVariant,
+        w: u32,
+        h: u32,
+        mode: RenderMode,
+    ) -> Option<Vec<Spans<'static>>> {
+        let anim_key = AnimationKey {
+            id,
+            shiny: variant.shiny,
+            w,
+            h,
+        };
+        let (frame, frames) = self.animation_frame(anim_key, variant).unzip();
+        let key = SpriteMemoKey {
+            id,
+            variant,
+            w,
+            h,
+            mode,
+            bg: self.sprite_bg,
+            palette: self.palette,
+            frame,
+        };
+        self.sprite_memo_clock += 1;
+        if let Some((lines, stamp)) = self.sprite_memo.get_mut(&key) {
+            *stamp = self.sprite_memo_clock;
+            return Some(lines.clone());
+        }
+
+        let pixels = match frame {
+            Some(i) => self
+                .frame_cache
+                .lock()
+                .unwrap()
+                .get(&anim_key)
+                .flatten()
+                .and_then(|anim| anim.frames[i].rows(w, h)),
+            None => self.get_sprite_pixels(id, variant, w, h),
+        }?;
+        let lines = render::sprite_lines(&pixels, mode, self.sprite_bg, &self.palette);
+        if frames.is_some_and(|n| n > MAX_MEMO_FRAMES) {
+            return Some(lines);
+        }
+        if self.sprite_memo.len() >= SPRITE_MEMO_ENTRIES {
+            let oldest = self
+                .sprite_memo
+                .iter()
+                .min_by_key(|(_, (_, stamp))| *stamp)
+                .map(|(k, _)| *k);
+            if let Some(k) = oldest {
+                self.sprite_memo.remove(&k);
             }
-            let resized = image::imageops::resize(&buf, w, h, FilterType::Lanczos3);
-            let mut rows: Vec<Vec<(u8, u8, u8)>> = Vec::with_capacity(resized.height() as usize);
-            for y in 0..resized.height() {
-                let mut row = Vec::with_capacity(resized.width() as usize);
-                for x in 0..resized.width() {
-                    let p = resized.get_pixel(x, y);
-                    row.push((p[0], p[1], p[2]));
-                }
-                rows.push(row);
+        }
+        self.sprite_memo
+            .insert(key, (lines.clone(), self.sprite_memo_clock));
+        Some(lines)
+    }
+
+    /// Advance the animation of the selected Pokémon by `elapsed` (called from
+    /// the tick loop). Frames are skipped when their delay is shorter than a tick.
+    pub fn on_tick(&mut self, elapsed: std::time::Duration) {
+        if self.anim_paused || self.visible.is_empty() {
+            return;
+        }
+        let Some((w, h)) = self.sprite_size else {
+            return;
+        };
+        let key = AnimationKey {
+            id: self.all_pokemons[self.visible[self.selected_visible]].pokedex,
+            shiny: self.sprite_variant.shiny,
+            w,
+            h,
+        };
+        let delays = match self.frame_cache.lock().unwrap().get(&key) {
+            Some(Some(anim)) => anim.delays.clone(),
+            _ => return,
+        };
+        self.anim_elapsed += elapsed;
+        while let Some(&delay) = delays.get(self.anim_frame % delays.len()) {
+            if self.anim_elapsed < delay {
+                break;
             }
-            return Some(rows);
+            self.anim_elapsed -= delay;
+            self.anim_frame = (self.anim_frame + 1) % delays.len();
         }
+    }
 
+    /// Encoded sequence for the current graphic request, asking the loader to
+    /// encode it on a miss.
+    fn graphic_sequence(&mut self) -> Option<String> {
+        let req = self.graphic_request.clone()?;
+        if let Some(seq) = self.graphic_cache.lock().unwrap().get(&req) {
+            return Some(seq.clone());
+        }
+        if let Some(loader) = &self.loader {
+            loader.request(LoadRequest::Graphic(req));
+            self.sprite_loading = true;
+        }
         None
     }
 
+    /// Show the calculator tab and start editing it.
+    pub fn edit_calculator(&mut self) {
+        self.detail_tab = DetailTab::Calculator;
+        self.detail_scroll = 0;
+        self.calc_editing = true;
+    }
+
+    /// Move the calculator cursor by `delta` fields, wrapping around.
+    pub fn calc_move(&mut self, delta: isize) {
+        self.calc_field =
+            (self.calc_field as isize + delta).rem_euclid(CalcField::COUNT as isize) as usize;
+    }
+
+    pub fn calc_adjust(&mut self, delta: i32) {
+        self.calc
+            .adjust(CalcField::from_index(self.calc_field), delta);
+    }
+
+    /// Set the current field to its lowest or highest value.
+    pub fn calc_extreme(&mut self, highest: bool) {
+        let field = CalcField::from_index(self.calc_field);
+        let value = match (field, highest) {
+            (_, false) => 0,
+            (CalcField::Nature, true) => NATURES.len() as u32 - 1,
+            (_, true) => u32::MAX,
+        };
+        self.calc.set(field, value);
+    }
+
+    /// Type a digit into the current field. A digit that would push the value
+    /// out of range starts a new number instead.
+    pub fn calc_digit(&mut self, digit: u32) {
+        let field = CalcField::from_index(self.calc_field);
+        if field == CalcField::Nature {
+            return;
+        }
+        let typed = self
+            .calc
+            .get(field)
+            .saturating_mul(10)
+            .saturating_add(digit);
+        self.calc.set(field, typed);
+        if self.calc.get(field) != typed {
+            self.calc.set(field, digit);
+        }
+    }
+
+    pub fn calc_backspace(&mut self) {
+        let field = CalcField::from_index(self.calc_field);
+        if field != CalcField::Nature {
+            self.calc.set(field, self.calc.get(field) / 10);
+        }
+    }
+
+    /// Cycle the radar overlay: off, dex average, pinned Pokémon (if any).
+    pub fn cycle_radar_overlay(&mut self) {
+        self.radar_overlay = match self.radar_overlay {
+            RadarOverlay::Off => RadarOverlay::DexAverage,
+            RadarOverlay::DexAverage if self.radar_pinned.is_some() => RadarOverlay::Pinned,
+            _ => RadarOverlay::Off,
+        };
+    }
+
+    /// Pin the selected Pokémon as the radar overlay and show the radar tab.
+    pub fn pin_radar(&mut self) {
+        if let Some(&i) = self.visible.get(self.selected_visible) {
+            self.radar_pinned = Some(self.all_pokemons[i].pokedex);
+            self.radar_overlay = RadarOverlay::Pinned;
+            self.detail_tab = DetailTab::Radar;
+        }
+    }
+
+    /// Mark or unmark the selected Pokémon for comparison. Marking a fifth one
+    /// drops the oldest mark.
+    pub fn toggle_mark(&mut self) {
+        let id = match self.visible.get(self.selected_visible) {
+            Some(&i) => self.all_pokemons[i].pokedex,
+            None => return,
+        };
+        if let Some(pos) = self.marked.iter().position(|&m| m == id) {
+            self.marked.remove(pos);
+        } else {
+            if self.marked.len() == MAX_COMPARED {
+                self.marked.remove(0);
+            }
+            self.marked.push(id);
+        }
+    }
+
+    fn reset_animation(&mut self) {
+        self.anim_frame = 0;
+        self.anim_elapsed = std::time::Duration::ZERO;
+    }
+
+    /// Swap in freshly fetched data. Rendered sprites and decoded animations are
+    /// dropped since a refresh may have downloaded sprites that previously fell
+    /// back to another variant or were missing.
+    pub fn set_pokemons(&mut self, all: Vec<Pokemon>) {
+        self.all_pokemons = all;
+        self.stats_index = StatsIndex::build(&self.all_pokemons);
+        self.text_index = TextIndex::build(&self.all_pokemons);
+        self.sprite_memo.clear();
+        self.frame_cache.lock().unwrap().clear();
+        if let Some(loader) = &self.loader {
+            loader.reset_failed();
+        }
+        self.apply_filter();
+    }
+
     pub fn next(&mut self) {
         if !self.visible.is_empty() {
             self.selected_visible = (self.selected_visible + 1) % self.visible.len();
+            self.detail_scroll = 0;
+            self.reset_animation();
         }
     }
 
@@ -152,33 +747,446 @@ impl App {
             } else {
                 self.selected_visible -= 1;
             }
+            self.detail_scroll = 0;
+            self.reset_animation();
         }
     }
 
+    pub fn next_tab(&mut self) {
+        self.detail_tab = self.detail_tab.next();
+        self.detail_scroll = 0;
+    }
 
+    pub fn previous_tab(&mut self) {
+        self.detail_tab = self.detail_tab.previous();
+        self.detail_scroll = 0;
+    }
 
-    pub fn apply_filter(&mut self) {
-        let q = self.search_query.to_lowercase();
-        if q.is_empty() {
-            self.visible = (0..self.all_pokemons.len()).collect();
-        } else {
-            self.visible = self
-                .all_pokemons
+    /// Cycle the encounter version filter through every version that appears in
+    /// the loaded data (in release order), then back to "all versions".
+    pub fn cycle_encounter_version(&mut self) {
+        let mut versions: Vec<&str> = self
+            .all_pokemons
+            .iter()
+            .filter_map(|p| p.encounters.as_ref())
+            .flatten()
+            .map(|e| e.version.as_str())
+            .collect();
+        versions.sort_by_key(|v| (version_rank(v), *v));
+        versions.dedup();
+
+        self.encounter_version = match &self.encounter_version {
+            None => versions.first().map(|v| v.to_string()),
+            Some(cur) => versions
                 .iter()
-                .enumerate()
-                .filter_map(|(i, p)| {
-                    if p.name.to_lowercase().contains(&q)
-                        || p.types.iter().any(|t| t.to_lowercase().contains(&q))
-                    {
-                        Some(i)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+                .position(|v| v == cur)
+                .and_then(|i| versions.get(i + 1))
+                .map(|v| v.to_string()),
+        };
+        self.detail_scroll = 0;
+    }
+
+    /// PokeAPI name of the selected Pokédex (e.g. `national`, `kanto`).
+    pub fn dex_name(&self) -> &'static str {
+        DEXES[self.dex].0
+    }
+
+    /// Switch to the next Pokédex; the list is re-filtered and re-ordered by
+    /// regional number.
+    pub fn cycle_dex(&mut self) {
+        self.dex = (self.dex + 1) % DEXES.len();
+        self.apply_filter();
+    }
+
+    pub fn scroll_detail(&mut self, delta: i32) {
+        self.detail_scroll = (self.detail_scroll as i32 + delta).max(0) as u16;
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+        self.help_scroll = 0;
+    }
+
+    pub fn scroll_help(&mut self, delta: i32) {
+        self.help_scroll = (self.help_scroll as i32 + delta).max(0) as u16;
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort_key = self.sort_key.next();
+        self.apply_filter();
+    }
+
+    pub fn flip_sort(&mut self) {
+        self.sort_desc = !self.sort_desc;
+        self.apply_filter();
+    }
+
+    /// Select the listed Pokémon with the typed number (in the current dex)
+    /// and close the go-to prompt. The list itself is left as filtered; if no
+    /// listed Pokémon has that number the prompt stays open with an error.
+    pub fn finish_goto(&mut self) {
+        let Some(target) = self.goto_input.as_ref().and_then(|s| s.parse::<u32>().ok()) else {
+            self.goto_input = None;
+            self.goto_error = None;
+            return;
+        };
+        let dex = self.dex_name();
+        let Some(pos) = self
+            .visible
+            .iter()
+            .position(|&i| self.all_pokemons[i].dex_number(dex) == Some(target))
+        else {
+            self.goto_error = Some(format!("#{} not in list", target));
+            return;
+        };
+        self.goto_input = None;
+        self.goto_error = None;
+        if pos != self.selected_visible {
+            self.selected_visible = pos;
+            self.detail_scroll = 0;
+            self.reset_animation();
         }
+    }
 
-        if self.visible.is_empty() {
+    /// Enter search mode, keeping the current search to edit (it is also
+    /// what Down returns to after recalling history).
+    pub fn start_search(&mut self) {
+        self.search_mode = true;
+        self.history_pos = None;
+    }
+
+    /// Leave search mode; the search stays applied and, if it parses, is added
+    /// to the history.
+    pub fn finish_search(&mut self) {
+        self.search_mode = false;
+        self.reverse_search = None;
+        if self.query_error.is_some() {
+            return;
+        }
+        history::push(&mut self.search_history, &self.search_query);
+        self.history_error = history::save(&self.search_history)
+            .err()
+            .map(|e| format!("Couldn't save history: {}", e));
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        self.search_query.push(c);
+        self.history_pos = None;
+        self.apply_filter();
+    }
+
+    pub fn search_pop(&mut self) {
+        self.search_query.pop();
+        self.history_pos = None;
+        self.apply_filter();
+    }
+
+    pub fn clear_search(&mut self) {
+        self.search_query.clear();
+        self.history_pos = None;
+        self.apply_filter();
+    }
+
+    /// Replace the search with the previous (`older`) or next history entry.
+    /// Going past the newest entry brings back what was typed before.
+    pub fn recall_history(&mut self, older: bool) {
+        let len = self.search_history.len();
+        let pos = match (self.history_pos, older) {
+            (None, true) if len > 0 => {
+                self.history_draft = self.search_query.clone();
+                Some(len - 1)
+            }
+            (Some(p), true) => Some(p.saturating_sub(1)),
+            (Some(p), false) if p + 1 < len => Some(p + 1),
+            (Some(_), false) => None,
+            (None, _) => return,
+        };
+        self.history_pos = pos;
+        self.search_query = match pos {
+            Some(p) => self.search_history[p].clone(),
+            None => std::mem::take(&mut self.history_draft),
+        };
+        self.apply_filter();
+    }
+
+    /// Start a Ctrl-R search, or look further back if one is running.
+    pub fn reverse_search_older(&mut self) {
+        match &self.reverse_search {
+            Some(rs) => {
+                let before = rs.found.unwrap_or(self.search_history.len());
+                let pattern = rs.pattern.clone();
+                if let Some(i) = self.find_in_history(&pattern, before) {
+                    self.show_reverse_match(Some(i));
+                }
+            }
+            None => {
+                self.reverse_search = Some(ReverseSearch {
+                    pattern: String::new(),
+                    found: None,
+                    draft: self.search_query.clone(),
+                });
+            }
+        }
+    }
+
+    pub fn reverse_search_push(&mut self, c: char) {
+        if let Some(rs) = self.reverse_search.as_mut() {
+            rs.pattern.push(c);
+            self.research();
+        }
+    }
+
+    pub fn reverse_search_pop(&mut self) {
+        if let Some(rs) = self.reverse_search.as_mut() {
+            rs.pattern.pop();
+            self.research();
+        }
+    }
+
+    /// Search again from the newest entry after the pattern changed.
+    fn research(&mut self) {
+        let pattern = match &self.reverse_search {
+            Some(rs) => rs.pattern.clone(),
+            None => return,
+        };
+        let found = self.find_in_history(&pattern, self.search_history.len());
+        self.show_reverse_match(found);
+    }
+
+    /// Newest history entry before index `before` containing `pattern`
+    /// (case-insensitive).
+    fn find_in_history(&self, pattern: &str, before: usize) -> Option<usize> {
+        let pattern = pattern.to_lowercase();
+        self.search_history[..before]
+            .iter()
+            .rposition(|e| e.to_lowercase().contains(&pattern))
+    }
+
+    /// Show the matched entry in the search box (and filter by it).
+    fn show_reverse_match(&mut self, found: Option<usize>) {
+        if let Some(rs) = self.reverse_search.as_mut() {
+            rs.found = found;
+            if let Some(i) = found {
+                self.search_query = self.search_history[i].clone();
+                self.apply_filter();
+            }
+        }
+    }
+
+    /// End the Ctrl-R search, keeping the match in the search box to edit.
+    pub fn accept_reverse_search(&mut self) {
+        self.reverse_search = None;
+        self.history_pos = None;
+    }
+
+    /// End the Ctrl-R search and restore what was typed before it.
+    pub fn cancel_reverse_search(&mut self) {
+        if let Some(rs) = self.reverse_search.take() {
+            self.search_query = rs.draft;
+            self.apply_filter();
+        }
+    }
+
+    /// (Re)load `presets.json`; on an error the picker shows it and saving is
+    /// refused until the file is fixed or removed.
+    pub fn load_presets(&mut self) {
+        match presets::load() {
+            Ok(presets) => {
+                self.presets = presets;
+                self.presets_error = None;
+            }
+            Err(err) => {
+                self.presets = Vec::new();
+                self.presets_error = Some(err);
+            }
+        }
+    }
+
+    /// Write the presets, returning the message to show if that failed.
+    fn save_presets(&self) -> Option<String> {
+        if let Some(err) = &self.presets_error {
+            return Some(format!("{} (fix or remove it to save presets)", err));
+        }
+        presets::save(&self.presets)
+            .err()
+            .map(|e| format!("Couldn't save presets: {}", e))
+    }
+
+    pub fn open_presets(&mut self) {
+        if self.presets_error.is_some() {
+            self.load_presets();
+        }
+        let active = self
+            .preset
+            .as_ref()
+            .and_then(|(name, _)| self.presets.iter().position(|p| &p.name == name));
+        self.preset_picker = Some(PresetPicker {
+            selected: active.map_or(0, |i| i + 1),
+            message: self.presets_error.clone(),
+            ..PresetPicker::default()
+        });
+    }
+
+    pub fn preset_move(&mut self, delta: i32) {
+        let rows = self.presets.len() as i32 + 1;
+        if let Some(picker) = self.preset_picker.as_mut() {
+            picker.selected = (picker.selected as i32 + delta).rem_euclid(rows) as usize;
+        }
+    }
+
+    /// Apply the highlighted preset (or none) and close the picker. A preset
+    /// whose query doesn't parse stays open with the error.
+    pub fn apply_preset(&mut self) {
+        let Some(picker) = self.preset_picker.as_mut() else {
+            return;
+        };
+        let preset = match picker
+            .selected
+            .checked_sub(1)
+            .and_then(|i| self.presets.get(i))
+        {
+            None => None,
+            Some(p) => match Query::parse(&p.query) {
+                Ok(q) => q.map(|q| (p.name.clone(), q)),
+                Err(err) => {
+                    picker.message = Some(format!("{}: {}", p.name, err));
+                    return;
+                }
+            },
+        };
+        self.preset = preset;
+        self.preset_picker = None;
+        self.apply_filter();
+    }
+
+    /// Start naming a new preset for the live search, if there is a valid one.
+    pub fn start_saving_preset(&mut self) {
+        let message = if self.presets_error.is_some() {
+            self.save_presets()
+        } else if self.search_query.trim().is_empty() {
+            Some("Type a search with '/' first".to_string())
+        } else {
+            Query::parse(&self.search_query).err()
+        };
+        if let Some(picker) = self.preset_picker.as_mut() {
+            match message {
+                Some(msg) => picker.message = Some(msg),
+                None => picker.naming = Some(String::new()),
+            }
+        }
+    }
+
+    /// Save the live search under the typed name, replacing a preset with the
+    /// same name.
+    pub fn finish_saving_preset(&mut self) {
+        let Some(name) = self
+            .preset_picker
+            .as_mut()
+            .and_then(|picker| picker.naming.take())
+        else {
+            return;
+        };
+        let name = name.trim().to_string();
+        if name.is_empty() || self.presets_error.is_some() {
+            return;
+        }
+        let preset = Preset {
+            name,
+            query: self.search_query.trim().to_string(),
+        };
+        let index = presets::upsert(&mut self.presets, preset);
+        let message = self.save_presets();
+        if let Some(picker) = self.preset_picker.as_mut() {
+            picker.selected = index + 1;
+            picker.message = message;
+        }
+    }
+
+    /// Delete the highlighted preset, un-applying it if it is active.
+    pub fn delete_preset(&mut self) {
+        let Some(i) = self
+            .preset_picker
+            .as_ref()
+            .and_then(|picker| picker.selected.checked_sub(1))
+        else {
+            return;
+        };
+        if i >= self.presets.len() || self.presets_error.is_some() {
+            return;
+        }
+        let removed = self.presets.remove(i);
+        let message = self.save_presets();
+        if let Some(picker) = self.preset_picker.as_mut() {
+            picker.selected = picker.selected.min(self.presets.len());
+            picker.message = message;
+        }
+        if self
+            .preset
+            .as_ref()
+            .is_some_and(|(name, _)| *name == removed.name)
+        {
+            self.preset = None;
+            self.apply_filter();
+        }
+    }
+
+    /// Recompute the visible list from the search query, applied preset,
+    /// selected dex and sort order, keeping the selected Pokémon selected if it
+    /// is still listed.
+    pub fn apply_filter(&mut self) {
+        let selected = self.visible.get(self.selected_visible).copied();
+        match Query::parse(&self.search_query) {
+            Ok(query) => {
+                self.query = query;
+                self.query_error = None;
+            }
+            Err(err) => self.query_error = Some(err),
+        }
+        if let Some(q) = self.query.as_mut() {
+            q.resolve_text(&self.text_index);
+        }
+        if let Some((_, q)) = self.preset.as_mut() {
+            q.resolve_text(&self.text_index);
+        }
+        let dex = self.dex_name();
+        self.visible = self
+            .all_pokemons
+            .iter()
+            .enumerate()
+            .filter_map(|(i, p)| {
+                // Skip Pokémon that have no entry in the selected dex.
+                p.dex_number(dex)?;
+                let matches = |q: &Query| q.matches(p, dex);
+                if self.query.as_ref().is_none_or(matches)
+                    && self.preset.as_ref().is_none_or(|(_, q)| matches(q))
+                {
+                    Some(i)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let all = &self.all_pokemons;
+        let (key, desc) = (self.sort_key, self.sort_desc);
+        self.visible.sort_by(|&a, &b| {
+            let order = key.compare(&all[a], &all[b], dex);
+            if desc {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        // Fuzzy name searches list the best matches first; the sort order only
+        // breaks ties (the sort is stable).
+        if let Some(q) = self.query.as_ref().filter(|q| q.ranked()) {
+            self.visible.sort_by_cached_key(|&i| {
+                std::cmp::Reverse(q.relevance(&format_name(&all[i].name)))
+            });
+        }
+
+        if let Some(pos) = selected.and_then(|i| self.visible.iter().position(|&v| v == i)) {
+            self.selected_visible = pos;
+        } else if self.visible.is_empty() {
             self.selected_visible = 0;
         } else if self.selected_visible >= self.visible.len() {
             self.selected_visible = self.visible.len() - 1;
@@ -186,8 +1194,630 @@ impl App {
     }
 }
 
+/// Where the search words were found in `p`'s description, abilities and
+/// moves, with the matched words highlighted. Empty when nothing matched there.
+fn search_match_lines(app: &App, p: &Pokemon) -> Vec<Spans<'static>> {
+    let Some(query) = &app.query else {
+        return Vec::new();
+    };
+    let snippets: Vec<_> = query
+        .text_words()
+        .into_iter()
+        .flat_map(|w| textindex::snippets(p, w))
+        .collect();
+    if snippets.is_empty() {
+        return Vec::new();
+    }
+    let hit_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut lines = vec![Spans::from(Span::styled(
+        "Search matches:",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for s in snippets.iter().take(MAX_SNIPPETS) {
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{}: ", s.source),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::raw(s.before.clone()),
+            Span::styled(s.hit.clone(), hit_style),
+            Span::raw(s.after.clone()),
+        ]));
+    }
+    if snippets.len() > MAX_SNIPPETS {
+        lines.push(Spans::from(Span::styled(
+            format!("and {} more", snippets.len() - MAX_SNIPPETS),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.push(Spans::from(Span::raw("")));
+    lines
+}
+
+/// Approximate number of rows `lines` take when word-wrapped to `width`.
+fn wrapped_height(lines: &[Spans], width: u16) -> u16 {
+    lines
+        .iter()
+        .map(|line| {
+            let text: String = line.0.iter().map(|s| s.content.as_ref()).collect();
+            text_to_lines(&text, width.max(1) as usize).len().max(1) as u16
+        })
+        .sum()
+}
+
+fn description_lines(p: &Pokemon) -> Vec<Spans<'static>> {
+    let mut lines: Vec<Spans> = Vec::new();
+    lines.push(Spans::from(Span::styled(
+        "Description:",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for line in text_to_lines(&p.description, 60) {
+        lines.push(Spans::from(Span::raw(line)));
+    }
+    lines
+}
+
+/// Build the "Where to find" tab: encounters grouped by game version (in release
+/// order), optionally restricted to `version`.
+fn encounter_lines(p: &Pokemon, version: Option<&str>) -> Vec<Spans<'static>> {
+    let mut lines: Vec<Spans> = Vec::new();
+    if let Some(v) = version {
+        lines.push(Spans::from(Span::styled(
+            format!("Version: {}  (v to change)", format_name(v)),
+            Style::default().fg(Color::DarkGray),
+        )));
+        lines.push(Spans::from(Span::raw("")));
+    }
+    let encounters = match &p.encounters {
+        None => {
+            lines.push(Spans::from(Span::raw(
+                "Encounter data not fetched yet. Press 'r' to refresh.",
+            )));
+            return lines;
+        }
+        Some(e) => e,
+    };
+
+    let mut shown: Vec<_> = encounters
+        .iter()
+        .filter(|e| version.is_none_or(|v| e.version == v))
+        .collect();
+    if shown.is_empty() {
+        lines.push(Spans::from(Span::raw(match version {
+            Some(_) => "Not found in the wild in this version.",
+            None => "Not found in the wild.",
+        })));
+        return lines;
+    }
+    shown.sort_by(|a, b| {
+        (version_rank(&a.version), &a.version, &a.location_area).cmp(&(
+            version_rank(&b.version),
+            &b.version,
+            &b.location_area,
+        ))
+    });
+
+    let mut current: Option<&str> = None;
+    for e in shown {
+        if current != Some(e.version.as_str()) {
+            if current.is_some() {
+                lines.push(Spans::from(Span::raw("")));
+            }
+            lines.push(Spans::from(Span::styled(
+                format_name(&e.version),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+            current = Some(e.version.as_str());
+        }
+        let levels = if e.min_level == e.max_level {
+            format!("Lv {}", e.min_level)
+        } else {
+            format!("Lv {}-{}", e.min_level, e.max_level)
+        };
+        lines.push(Spans::from(Span::raw(format!(
+            "  {} - {}, {} ({}%)",
+            format_location(&e.location_area),
+            format_name(&e.method),
+            levels,
+            e.chance
+        ))));
+    }
+    lines
+}
+
+fn debug_lines(app: &App) -> Vec<Spans<'static>> {
+    let st = app.sprite_cache.lock().unwrap().stats();
+    let lookups = st.hits + st.misses;
+    let hit_rate = if lookups == 0 {
+        0.0
+    } else {
+        st.hits as f64 * 100.0 / lookups as f64
+    };
+    let kib = |b: usize| b / 1024;
+    vec![
+        Spans::from(Span::styled(
+            "Thumbnail cache",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Spans::from(Span::raw(format!(
+            "{} entries, {} / {} KiB",
+            st.entries,
+            kib(st.bytes),
+            kib(st.budget)
+        ))),
+        Spans::from(Span::raw(format!(
+            "hits {}  misses {}  ({:.0}%)",
+            st.hits, st.misses, hit_rate
+        ))),
+        Spans::from(Span::raw(format!("evictions {}", st.evictions))),
+        Spans::from(Span::raw("")),
+        Spans::from(Span::raw(format!(
+            "animation frames {} KiB",
+            kib(app.frame_cache.lock().unwrap().used())
+        ))),
+    ]
+}
+
+/// Lines of the stat calculator tab: the build being edited and the actual
+/// stats it gives `p`, with the field under the cursor highlighted while editing.
+fn calculator_lines(app: &App, p: &Pokemon) -> Vec<Spans<'static>> {
+    let build = &app.calc;
+    let cursor = app
+        .calc_editing
+        .then(|| CalcField::from_index(app.calc_field));
+    let cell = |field: CalcField, text: String| {
+        if cursor == Some(field) {
+            Span::styled(text, Style::default().add_modifier(Modifier::REVERSED))
+        } else {
+            Span::raw(text)
+        }
+    };
+    let effect = calc::nature_effect(build.nature);
+    let nature_desc = match effect {
+        Some((up, down)) => format!(
+            " (+{} -{})",
+            stat_label(STAT_NAMES[up]),
+            stat_label(STAT_NAMES[down])
+        ),
+        None => " (neutral)".to_string(),
+    };
+
+    let mut lines = vec![
+        Spans::from(vec![
+            Span::raw("Level  "),
+            cell(CalcField::Level, format!("{:>3}", build.level)),
+            Span::raw("   Nature "),
+            cell(CalcField::Nature, NATURES[build.nature].to_string()),
+            Span::raw(nature_desc),
+        ]),
+        Spans::from(Span::raw("")),
+        Spans::from(Span::styled(
+            format!(
+                "{:<5}{:>5}{:>5}{:>5}{:>7}",
+                "Stat", "Base", "IV", "EV", "Value"
+            ),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+    ];
+    for (i, name) in STAT_NAMES.iter().enumerate() {
+        let st = match p.stats.iter().find(|s| s.name == *name) {
+            Some(st) => st,
+            None => continue,
+        };
+        let marker = match effect {
+            Some((up, _)) if up == i => "+",
+            Some((_, down)) if down == i => "-",
+            _ => "",
+        };
+        let value = build.calc(st).map_or(String::new(), |v| v.to_string());
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{:<5}{:>5}  ", stat_label(name) + marker, st.base)),
+            cell(CalcField::Iv(i), format!("{:>3}", build.ivs[i])),
+            Span::raw("  "),
+            cell(CalcField::Ev(i), format!("{:>3}", build.evs[i])),
+            Span::styled(
+                format!("{:>7}", value),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+        ]));
+    }
+    lines.push(Spans::from(Span::raw("")));
+    lines.push(Spans::from(Span::raw(format!(
+        "EVs {}/{}",
+        build.ev_total(),
+        calc::MAX_EV_TOTAL
+    ))));
+    lines.push(Spans::from(Span::styled(
+        if app.calc_editing {
+            "Up/Down field, Left/Right adjust, 0-9 type, Home/End min/max, Esc done"
+        } else {
+            "Press c to edit"
+        },
+        Style::default().fg(Color::DarkGray),
+    )));
+    lines
+}
+
+/// Stat values given by `base` in radar axis order, as fractions of the bar scale.
+fn radar_values(app: &App, base: impl Fn(&str) -> Option<f64>) -> [f64; 6] {
+    radar::AXES.map(|i| {
+        let name = STAT_NAMES[i];
+        base(name).unwrap_or(0.0) / app.stats_index.bar_max(name, app.stat_scale) as f64
+    })
+}
+
+/// Radar chart of `p`'s base stats with the selected overlay behind it.
+fn radar_widget<'a>(app: &App, p: &Pokemon, area: Rect) -> Canvas<'a, impl Fn(&mut Context) + 'a> {
+    let base_of = |p: &Pokemon, name: &str| {
+        p.stats
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.base as f64)
+    };
+    let mut series = Vec::new();
+    let mut title = format!("Radar: {}", format_name(&p.name));
+    match app.radar_overlay {
+        RadarOverlay::Off => {}
+        RadarOverlay::DexAverage => {
+            series.push(RadarSeries {
+                values: radar_values(app, |name| {
+                    app.stats_index.stat(name).map(|s| s.mean as f64)
+                }),
+                color: Color::Cyan,
+            });
+            title.push_str(" vs dex average");
+        }
+        RadarOverlay::Pinned => {
+            if let Some(other) = app
+                .radar_pinned
+                .and_then(|id| app.all_pokemons.iter().find(|o| o.pokedex == id))
+            {
+                series.push(RadarSeries {
+                    values: radar_values(app, |name| base_of(other, name)),
+                    color: Color::Cyan,
+                });
+                title.push_str(&format!(" vs {}", format_name(&other.name)));
+            }
+        }
+    }
+    series.push(RadarSeries {
+        values: radar_values(app, |name| base_of(p, name)),
+        color: Color::Yellow,
+    });
+    let labels = radar::AXES.map(|i| {
+        let name = STAT_NAMES[i];
+        match base_of(p, name) {
+            Some(v) => format!("{} {}", stat_label(name), v),
+            None => stat_label(name),
+        }
+    });
+    radar::radar_chart(
+        Block::default().borders(Borders::ALL).title(title),
+        area,
+        labels,
+        series,
+    )
+}
+
+/// The search box: the query being typed or applied, with the parse error (if
+/// any) in the title, or a hint when there is no search.
+fn search_box(app: &App) -> Paragraph<'static> {
+    if let Some(input) = &app.goto_input {
+        let title = match &app.goto_error {
+            Some(err) => Span::styled(
+                format!("Go to number: {}", err),
+                Style::default().fg(Color::Red),
+            ),
+            None => Span::raw("Go to number (Enter to jump, Esc to cancel)"),
+        };
+        return Paragraph::new(vec![Spans::from(Span::raw(format!("#{}", input)))])
+            .block(Block::default().borders(Borders::ALL).title(title));
+    }
+    if !app.search_mode && app.search_query.trim().is_empty() {
+        return Paragraph::new(vec![Spans::from(Span::raw(
+            "Press '/' to search, e.g. type:fire spe>100 -legendary or #1-151; 'g' goes to a number",
+        ))])
+        .block(Block::default().borders(Borders::ALL).title("Search"));
+    }
+    if let Some(rs) = &app.reverse_search {
+        let label = if rs.found.is_some() || rs.pattern.is_empty() {
+            "reverse-search"
+        } else {
+            "failed reverse-search"
+        };
+        return Paragraph::new(vec![Spans::from(vec![
+            Span::styled(
+                format!("({})`{}': ", label, rs.pattern),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(app.search_query.clone()),
+        ])])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Search history (Ctrl-R older, Enter keep, Esc cancel)"),
+        );
+    }
+    let title = match (
+        app.query_error.as_ref().or(app.history_error.as_ref()),
+        app.search_mode,
+    ) {
+        (Some(err), _) => Span::styled(format!("Search: {}", err), Style::default().fg(Color::Red)),
+        (None, true) => Span::raw("Search"),
+        (None, false) => Span::raw("Search ('/' to edit)"),
+    };
+    let prompt = if app.search_mode { "/" } else { "" };
+    Paragraph::new(vec![Spans::from(Span::raw(format!(
+        "{}{}",
+        prompt, app.search_query
+    )))])
+    .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// The preset picker popup: "no preset" and the saved presets with their
+/// queries, and below them the name being typed, an error or the key hints.
+fn draw_preset_picker(f: &mut Frame<CrosstermBackend<Stdout>>, app: &App, area: Rect) {
+    let Some(picker) = &app.preset_picker else {
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(area);
+
+    let active = app.preset.as_ref().map(|(name, _)| name.as_str());
+    let mut items = vec![ListItem::new(Spans::from(Span::raw(format!(
+        "{} (no preset)",
+        if active.is_none() { "*" } else { " " }
+    ))))];
+    for p in &app.presets {
+        items.push(ListItem::new(Spans::from(vec![
+            Span::raw(format!(
+                "{} {}  ",
+                if active == Some(p.name.as_str()) {
+                    "*"
+                } else {
+                    " "
+                },
+                p.name
+            )),
+            Span::styled(p.query.clone(), Style::default().fg(Color::DarkGray)),
+        ])));
+    }
+    let mut state = ratatui::widgets::ListState::default();
+    state.select(Some(picker.selected));
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Presets"))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        );
+    f.render_stateful_widget(list, chunks[0], &mut state);
+
+    let (title, line) = match (&picker.naming, &picker.message) {
+        (Some(name), _) => (
+            format!(
+                "Save '{}' as (Enter to save, Esc to cancel)",
+                app.search_query.trim()
+            ),
+            Span::raw(format!("{}_", name)),
+        ),
+        (None, Some(msg)) => (
+            String::new(),
+            Span::styled(msg.clone(), Style::default().fg(Color::Red)),
+        ),
+        (None, None) => (
+            String::new(),
+            Span::raw("Enter apply, s save current search, d delete, Esc close"),
+        ),
+    };
+    f.render_widget(
+        Paragraph::new(Spans::from(line))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+        chunks[1],
+    );
+}
+
+/// Side-by-side view of the marked Pokémon: sprite, types, abilities, base
+/// stats (the best value of each highlighted) and defensive matchups.
+fn draw_compare(f: &mut Frame<CrosstermBackend<Stdout>>, app: &mut App, area: Rect) {
+    let picked: Vec<Pokemon> = app
+        .marked
+        .iter()
+        .filter_map(|id| app.all_pokemons.iter().find(|p| p.pokedex == *id))
+        .cloned()
+        .collect();
+    if picked.len() < 2 {
+        let hint =
+            Paragraph::new("Mark two to four Pokémon with Space, then press C to compare them.")
+                .block(Block::default().borders(Borders::ALL).title("Compare"))
+                .wrap(Wrap { trim: true });
+        f.render_widget(hint, area);
+        return;
+    }
+
+    let base = |p: &Pokemon, name: &str| p.stats.iter().find(|s| s.name == name).map(|s| s.base);
+    // Highlight a value only when it beats at least one of the others.
+    let winner = |value: u32, all: Vec<u32>| {
+        let best = all.iter().copied().max().unwrap_or(0);
+        value == best && all.iter().any(|&v| v != best)
+    };
+    let win_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+
+    // Equal columns, with the last one taking any remainder.
+    let col_w = area.width / picked.len() as u16;
+    let mut constraints = vec![Constraint::Length(col_w); picked.len() - 1];
+    constraints.push(Constraint::Min(0));
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(area);
+    for (col, p) in columns.iter().zip(&picked) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(12), Constraint::Min(3)])
+            .split(*col);
+
+        // Graphics protocols draw a single image, so use half blocks here.
+        let mode = match app.render_mode {
+            RenderMode::Graphics(_) => RenderMode::HalfBlock,
+            mode => mode,
+        };
+        let (w, h) = mode.fit(
+            rows[0].width.saturating_sub(2) as u32,
+            rows[0].height.saturating_sub(2) as u32,
+        );
+        let sprite =
+            match app.sprite_spans(p.pokedex, app.sprite_variant, w.min(64), h.min(64), mode) {
+                Some(lines) => Paragraph::new(lines),
+                None if app.sprite_loading => Paragraph::new("(loading…)"),
+                None => Paragraph::new("(no sprite)"),
+            };
+        f.render_widget(
+            sprite.alignment(Alignment::Center).block(
+                Block::default().borders(Borders::ALL).title(format!(
+                    "{} (#{})",
+                    format_name(&p.name),
+                    p.pokedex
+                )),
+            ),
+            rows[0],
+        );
+
+        let mut lines = vec![Spans::from(type_badges(&p.types, &app.palette))];
+        if !p.abilities.is_empty() {
+            lines.push(Spans::from(Span::raw(p.abilities.join(", "))));
+        }
+        lines.push(Spans::from(Span::raw("")));
+        for name in STAT_NAMES {
+            if let Some(v) = base(p, name) {
+                let all = picked.iter().filter_map(|o| base(o, name)).collect();
+                lines.push(Spans::from(vec![
+                    Span::raw(format!("{:<5}", stat_label(name))),
+                    Span::styled(
+                        format!("{:>4}", v),
+                        if winner(v, all) {
+                            win_style
+                        } else {
+                            Style::default()
+                        },
+                    ),
+                ]));
+            }
+        }
+        let total = p.base_stat_total();
+        let totals = picked.iter().map(|o| o.base_stat_total()).collect();
+        lines.push(Spans::from(vec![
+            Span::raw(format!("{:<5}", "BST")),
+            Span::styled(
+                format!("{:>4}", total),
+                if winner(total, totals) {
+                    win_style
+                } else {
+                    Style::default().add_modifier(Modifier::BOLD)
+                },
+            ),
+        ]));
+
+        lines.push(Spans::from(Span::raw("")));
+        let matchups = defensive_matchups(&p.types);
+        for (mult, label, color) in [
+            (4.0, "x4", Color::Red),
+            (2.0, "x2", Color::LightRed),
+            (0.5, "x1/2", Color::LightGreen),
+            (0.25, "x1/4", Color::Green),
+            (0.0, "x0", Color::Gray),
+        ] {
+            let names: Vec<String> = matchups
+                .iter()
+                .filter(|(_, m)| *m == mult)
+                .map(|(t, _)| format_name(t))
+                .collect();
+            if !names.is_empty() {
+                lines.push(Spans::from(vec![
+                    Span::styled(format!("{:<5}", label), Style::default().fg(color)),
+                    Span::raw(names.join(", ")),
+                ]));
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL))
+                .wrap(Wrap { trim: true }),
+            rows[1],
+        );
+    }
+}
+
+/// Types as colored badges separated by spaces.
+fn type_badges(types: &[String], palette: &Palette) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for (i, t) in types.iter().enumerate() {
+        let (r, g, b) = type_rgb(t);
+        let bg = palette.color(r, g, b);
+        // choose contrasting foreground (black or white)
+        let lum = 0.2126 * (r as f32) + 0.7152 * (g as f32) + 0.0722 * (b as f32);
+        let fg = if lum > 160.0 {
+            Color::Black
+        } else {
+            Color::White
+        };
+        // small padded badge
+        spans.push(Span::styled(
+            format!(" {} ", format_name(t)),
+            Style::default().fg(fg).bg(bg),
+        ));
+        if i < types.len() - 1 {
+            spans.push(Span::raw(" "));
+        }
+    }
+    spans
+}
+
+/// Short column label of a PokeAPI stat name.
+fn stat_label(name: &str) -> String {
+    match name {
+        "hp" => "HP".to_string(),
+        "attack" => "ATK".to_string(),
+        "defense" => "DEF".to_string(),
+        "special-attack" => "SpA".to_string(),
+        "special-defense" => "SpD".to_string(),
+        "speed" => "SPD".to_string(),
+        other => {
+            // fallback: capitalize first letter
+            let mut c = other.chars();
+            match c.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
+            }
+        }
+    }
+}
+
+/// Red (low) through yellow to green (high) for a percentile rank, as dex
+/// sites color their stat bars.
+fn grade_color(pct: f32, palette: &Palette) -> Color {
+    let t = (pct / 100.0).clamp(0.0, 1.0);
+    let r = if t < 0.5 {
+        255.0
+    } else {
+        255.0 * (1.0 - t) * 2.0
+    };
+    let g = if t < 0.5 { 255.0 * t * 2.0 } else { 255.0 };
+    palette.color(r as u8, g as u8, 0)
+}
+
 pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App) -> io::Result<()> {
-    terminal
+    app.graphic_request = None;
+    app.sprite_loading = false;
+    // The frame just drawn, for repainting cells an old Sixel image covered.
+    let frame = terminal
         .draw(|f| {
             // helper to compute a centered rect for popups
             fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
@@ -208,22 +1838,70 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
                 .constraints([Constraint::Min(3), Constraint::Length(3)])
                 .split(chunks[0]);
 
+            let dex = app.dex_name();
             let items: Vec<ListItem> = app
                 .visible
                 .iter()
                 .filter_map(|&i| app.all_pokemons.get(i))
                 .map(|p| {
                     let display_name = format_name(&p.name);
-                    let lines = vec![Spans::from(vec![Span::raw(format!(
-                        "#{} {}",
-                        p.pokedex, display_name
-                    ))])];
-                    ListItem::new(lines)
+                    let number = p.dex_number(dex).unwrap_or(p.pokedex);
+                    let marked = app.marked.contains(&p.pokedex);
+                    let mut spans = vec![Span::raw(format!(
+                        "{}#{:03} ",
+                        if marked { "*" } else { " " },
+                        number
+                    ))];
+                    // Highlight the characters matched by a fuzzy search.
+                    let hits = app
+                        .query
+                        .as_ref()
+                        .map(|q| q.highlights(&display_name))
+                        .unwrap_or_default();
+                    // Underlined too, so it still shows on the highlighted row.
+                    let hit_style = Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                    for (i, c) in display_name.chars().enumerate() {
+                        let style = if hits.binary_search(&i).is_ok() {
+                            hit_style
+                        } else {
+                            Style::default()
+                        };
+                        // Extend the previous run of the same style (never the prefix).
+                        let len = spans.len();
+                        match spans.last_mut() {
+                            Some(last) if len > 1 && last.style == style => {
+                                last.content.to_mut().push(c)
+                            }
+                            _ => spans.push(Span::styled(c.to_string(), style)),
+                        }
+                    }
+                    let lines = vec![Spans::from(spans)];
+                    ListItem::new(lines).style(if marked {
+                        Style::default().fg(Color::Cyan)
+                    } else {
+                        Style::default()
+                    })
                 })
                 .collect();
 
+            let order = format!(
+                "{} {}",
+                app.sort_key.label(),
+                if app.sort_desc { "↓" } else { "↑" }
+            );
+            let mut scope = format!("{} Dex", DEXES[app.dex].1);
+            if let Some((name, _)) = &app.preset {
+                scope += &format!(", preset: {}", name);
+            }
+            let list_title = if app.query.as_ref().is_some_and(|q| q.ranked()) {
+                format!("Pokémon ({}, by relevance, then {})", scope, order)
+            } else {
+                format!("Pokémon ({}, by {})", scope, order)
+            };
             let list = List::new(items)
-                .block(Block::default().borders(Borders::ALL).title("Pokémon"))
+                .block(Block::default().borders(Borders::ALL).title(list_title))
                 .highlight_style(
                     Style::default()
                         .fg(Color::Yellow)
@@ -256,37 +1934,16 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
                         .ratio(pct);
                     f.render_widget(gauge, left_chunks[1]);
                 } else {
-                    let search_para = if app.search_mode {
-                        Paragraph::new(vec![Spans::from(Span::raw(format!(
-                            "/{}",
-                            app.search_query
-                        )))])
-                        .block(Block::default().borders(Borders::ALL).title("Search"))
-                    } else {
-                        Paragraph::new(vec![Spans::from(Span::raw(
-                            "Press '/' to search. Type to filter by name or type.",
-                        ))])
-                        .block(Block::default().borders(Borders::ALL).title("Search"))
-                    };
-                    f.render_widget(search_para, left_chunks[1]);
+                    f.render_widget(search_box(app), left_chunks[1]);
                 }
             } else {
-                let search_para = if app.search_mode {
-                    Paragraph::new(vec![Spans::from(Span::raw(format!(
-                        "/{}",
-                        app.search_query
-                    )))])
-                    .block(Block::default().borders(Borders::ALL).title("Search"))
-                } else {
-                    Paragraph::new(vec![Spans::from(Span::raw(
-                        "Press '/' to search. Type to filter by name or type.",
-                    ))])
-                    .block(Block::default().borders(Borders::ALL).title("Search"))
-                };
-                f.render_widget(search_para, left_chunks[1]);
+                f.render_widget(search_box(app), left_chunks[1]);
             }
 
-            let detail = if !app.visible.is_empty() {
+            let detail = if app.compare_mode {
+                draw_compare(f, app, chunks[1]);
+                Paragraph::new("").block(Block::default())
+            } else if !app.visible.is_empty() {
                 let sel_idx = app.visible[app.selected_visible];
                 let detail_chunks = Layout::default()
                     .direction(Direction::Vertical)
@@ -311,28 +1968,55 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
                     } else {
                         1
                     };
-                    let sprite_w = std::cmp::min(avail_w, 64u32);
-                    let sprite_h = std::cmp::min(avail_h, 64u32);
+                    let (sprite_w, sprite_h) = app.render_mode.fit(avail_w, avail_h);
+                    let sprite_w = std::cmp::min(sprite_w, 64u32);
+                    let sprite_h = std::cmp::min(sprite_h, 64u32);
 
                     // get pokedex id first (copy) then call mutable loader
                     let pokedex_id = app.all_pokemons[sel_idx].pokedex;
-                    if let Some(sprite_lines) = app.get_sprite_pixels(pokedex_id, sprite_w, sprite_h) {
-                        let mut stext: Vec<Spans> = Vec::new();
-                        for row in sprite_lines.iter() {
-                            let mut spans = Vec::new();
-                            for &(r, g, b) in row.iter() {
-                                spans.push(Span::styled(
-                                    " ",
-                                    Style::default().bg(Color::Rgb(r, g, b)),
-                                ));
-                            }
-                            stext.push(Spans::from(spans));
+                    app.sprite_size = Some((sprite_w, sprite_h));
+                    let sprite_title = match app.sprite_variant.label() {
+                        l if l.is_empty() => "Sprite".to_string(),
+                        l => format!("Sprite ({})", l),
+                    };
+                    if let RenderMode::Graphics(protocol) = app.render_mode {
+                        if let Some((_, path)) = app.sprite_variant.resolve(pokedex_id) {
+                            // Leave the cells blank; the image is written over them
+                            // after the frame is flushed.
+                            let cols = (sprite_w * 2) as u16;
+                            app.graphic_request = Some(GraphicRequest {
+                                protocol,
+                                path,
+                                area: Rect::new(
+                                    rect.x + 1 + (avail_w as u16).saturating_sub(cols) / 2,
+                                    rect.y + 1,
+                                    cols,
+                                    sprite_h as u16,
+                                ),
+                            });
+                            Paragraph::new("")
+                                .block(Block::default().borders(Borders::ALL).title(sprite_title))
+                        } else {
+                            Paragraph::new("(no sprite)")
+                                .block(Block::default().borders(Borders::ALL).title(sprite_title))
                         }
-                        Paragraph::new(stext)
-                            .block(Block::default().borders(Borders::ALL).title("Sprite"))
+                    } else if let Some(sprite_lines) = app.sprite_spans(
+                        pokedex_id,
+                        app.sprite_variant,
+                        sprite_w,
+                        sprite_h,
+                        app.render_mode,
+                    ) {
+                        Paragraph::new(sprite_lines)
+                            .alignment(Alignment::Center)
+                            .block(Block::default().borders(Borders::ALL).title(sprite_title))
+                    } else if app.sprite_loading {
+                        Paragraph::new("(loading…)")
+                            .alignment(Alignment::Center)
+                            .block(Block::default().borders(Borders::ALL).title(sprite_title))
                     } else {
                         Paragraph::new("(no sprite)")
-                            .block(Block::default().borders(Borders::ALL).title("Sprite"))
+                            .block(Block::default().borders(Borders::ALL).title(sprite_title))
                     }
                 } else {
                     Paragraph::new("(sprites off)")
@@ -342,50 +2026,23 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
 
                 let p = &app.all_pokemons[sel_idx];
                 let mut info_lines: Vec<Spans> = Vec::new();
+                let heading = match p.dex_number(dex) {
+                    Some(n) if dex != "national" => format!(
+                        "{} (#{}, {} #{})",
+                        format_name(&p.name),
+                        p.pokedex,
+                        DEXES[app.dex].1,
+                        n
+                    ),
+                    _ => format!("{} (#{})", format_name(&p.name), p.pokedex),
+                };
                 info_lines.push(Spans::from(Span::styled(
-                    format!("{} (#{})", format_name(&p.name), p.pokedex),
+                    heading,
                     Style::default().add_modifier(Modifier::BOLD),
                 )));
                 // Render types as colored badges
-                let mut type_spans: Vec<Span> = Vec::new();
-                type_spans.push(Span::raw("Types: "));
-                for (i, t) in p.types.iter().enumerate() {
-                    let t_l = t.to_lowercase();
-                    // color mapping using nicer RGB values per type
-                    let (r, g, b) = match t_l.as_str() {
-                        "normal" => (168, 168, 120),
-                        "fire" => (240, 128, 48),
-                        "water" => (104, 144, 240),
-                        "grass" => (120, 200, 80),
-                        "electric" => (248, 208, 48),
-                        "ice" => (152, 216, 216),
-                        "fighting" => (192, 48, 40),
-                        "poison" => (160, 64, 160),
-                        "ground" => (224, 192, 104),
-                        "flying" => (168, 144, 240),
-                        "psychic" => (248, 88, 136),
-                        "bug" => (168, 184, 32),
-                        "rock" => (184, 160, 56),
-                        "ghost" => (112, 88, 152),
-                        "dragon" => (112, 56, 248),
-                        "dark" => (112, 88, 72),
-                        "steel" => (184, 184, 208),
-                        "fairy" => (238, 153, 172),
-                        _ => (200, 200, 200),
-                    };
-                    let bg = Color::Rgb(r, g, b);
-                    // choose contrasting foreground (black or white)
-                    let lum = 0.2126 * (r as f32) + 0.7152 * (g as f32) + 0.0722 * (b as f32);
-                    let fg = if lum > 160.0 { Color::Black } else { Color::White };
-                    // small padded badge
-                    type_spans.push(Span::styled(
-                        format!(" {} ", format_name(t)),
-                        Style::default().fg(fg).bg(bg),
-                    ));
-                    if i < p.types.len() - 1 {
-                        type_spans.push(Span::raw(" "));
-                    }
-                }
+                let mut type_spans: Vec<Span> = vec![Span::raw("Types: ")];
+                type_spans.extend(type_badges(&p.types, &app.palette));
                 info_lines.push(Spans::from(type_spans));
                 if !p.abilities.is_empty() {
                     info_lines.push(Spans::from(Span::raw(format!(
@@ -404,11 +2061,11 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
 
                 let bottom_chunks = Layout::default()
                     .direction(Direction::Horizontal)
-                    .constraints([Constraint::Length(30), Constraint::Min(10)])
+                    .constraints([Constraint::Length(48), Constraint::Min(10)])
                     .split(detail_chunks[1]);
 
                 // Render per-stat horizontal bars aligned with each stat name.
-                // We'll draw lines with: NAME (padded) | VALUE | [bar...]
+                // We'll draw lines with: NAME | VALUE | PERCENTILE | [bar...] | LV50 | LV100
                 let stats_rect = bottom_chunks[0];
                 let inner_w = if stats_rect.width > 2 {
                     (stats_rect.width - 2) as usize
@@ -416,78 +2073,151 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
                     1usize
                 };
 
-                // Reserve columns: name (10), value (4), spaces (2)
-                let name_w = 10usize;
+                // Reserve columns: name (4), value (4), percentile (4), spaces (2),
+                // plus two level ranges (7 each, with a leading space) when they fit.
+                let name_w = 4usize;
                 let val_w = 4usize;
-                let reserved = name_w + val_w + 2;
-                let bar_max_w = inner_w.saturating_sub(reserved);
-
-                // find a global max across all Pokémon so bars are comparable across entries
-                let global_max = app
-                    .all_pokemons
-                    .iter()
-                    .flat_map(|pp| pp.stats.iter().map(|s| s.base))
-                    .max()
-                    .unwrap_or(1) as f32;
-                // cap scale to a reasonable upper bound (e.g., 255) and avoid zero
-                let scale_max = global_max.clamp(1.0, 255.0);
+                let range_w = 7usize;
+                let reserved = name_w + val_w + 4 + 2;
+                let show_levels = inner_w >= reserved + 2 * (range_w + 1) + 6;
+                let bar_max_w = inner_w
+                    .saturating_sub(reserved)
+                    .saturating_sub(if show_levels { 2 * (range_w + 1) } else { 0 });
+                let dim = Style::default().fg(Color::DarkGray);
+                let level_cols = |lv50: String, lv100: String| {
+                    if show_levels {
+                        format!(" {:>range_w$} {:>range_w$}", lv50, lv100)
+                    } else {
+                        String::new()
+                    }
+                };
 
-                let mut stat_lines: Vec<Spans> = Vec::new();
+                let mut stat_lines: Vec<Spans> = vec![Spans::from(Span::styled(
+                    format!(
+                        "{:<name_w$}{:>val_w$} {:>4} {:bar_max_w$}{}",
+                        "",
+                        "Base",
+                        "%ile",
+                        "",
+                        level_cols("Lv50".into(), "Lv100".into()),
+                    ),
+                    dim,
+                ))];
                 for st in p.stats.iter() {
-                    // short name/abbrev
-                    let nm = match st.name.as_str() {
-                        "hp" => "HP".to_string(),
-                        "attack" => "ATK".to_string(),
-                        "defense" => "DEF".to_string(),
-                        "special-attack" => "SpA".to_string(),
-                        "special-defense" => "SpD".to_string(),
-                        "speed" => "SPD".to_string(),
-                        other => {
-                            // fallback: capitalize first letter
-                            let mut c = other.chars();
-                            match c.next() {
-                                None => String::new(),
-                                Some(first) => first.to_uppercase().collect::<String>() + c.as_str(),
-                            }
-                        }
+                    let scale_max = app.stats_index.bar_max(&st.name, app.stat_scale) as f32;
+                    let bar_pos = |v: f32| ((v / scale_max) * (bar_max_w as f32)).round() as usize;
+                    let bar_len = bar_pos(st.base as f32).min(bar_max_w);
+                    // Mark the dex average of this stat: a gap inside the bar, or a
+                    // tick past its end.
+                    let mean_pos = app
+                        .stats_index
+                        .stat(&st.name)
+                        .map(|summary| bar_pos(summary.mean).min(bar_max_w.saturating_sub(1)));
+                    let mark = |i: usize| match (Some(i) == mean_pos, i < bar_len) {
+                        (true, true) => '│',
+                        (true, false) => '┊',
+                        (false, true) => '█',
+                        (false, false) => ' ',
                     };
+                    let bar: String = (0..bar_len).map(mark).collect();
+                    let rest: String = (bar_len..bar_max_w).map(mark).collect();
 
-                    let bar_len = if scale_max <= 0.0 {
-                        0
-                    } else {
-                        (((st.base as f32) / scale_max) * (bar_max_w as f32)).round() as usize
-                    };
-                    let bar = "█".repeat(bar_len);
-
-                    let line = format!(
-                        "{:<name_w$} {:>val_w$} {}",
-                        nm,
-                        st.base,
-                        bar,
-                        name_w = name_w,
-                        val_w = val_w
-                    );
-                    stat_lines.push(Spans::from(Span::raw(line)));
+                    let pct = app.stats_index.percentile(&st.name, st.base);
+                    let (lo50, hi50) = stat_range(&st.name, st.base, 50);
+                    let (lo100, hi100) = stat_range(&st.name, st.base, 100);
+                    let color = pct.map_or(Color::Reset, |pct| grade_color(pct, &app.palette));
+                    stat_lines.push(Spans::from(vec![
+                        Span::raw(format!(
+                            "{:<name_w$}{:>val_w$} {:>4} ",
+                            stat_label(&st.name),
+                            st.base,
+                            pct.map_or(String::new(), |pct| format!("{:.0}%", pct)),
+                        )),
+                        Span::styled(bar, Style::default().fg(color)),
+                        Span::styled(rest, dim),
+                        Span::raw(level_cols(
+                            format!("{}-{}", lo50, hi50),
+                            format!("{}-{}", lo100, hi100),
+                        )),
+                    ]));
+                }
+
+                if !p.stats.is_empty() {
+                    let total = p.base_stat_total();
+                    let pct = app.stats_index.bst_percentile(total);
+                    stat_lines.push(Spans::from(Span::styled(
+                        format!(
+                            "{:<name_w$}{:>val_w$} {:>4}",
+                            "BST",
+                            total,
+                            pct.map_or(String::new(), |pct| format!("{:.0}%", pct)),
+                        ),
+                        Style::default()
+                            .fg(pct.map_or(Color::Reset, |pct| grade_color(pct, &app.palette)))
+                            .add_modifier(Modifier::BOLD),
+                    )));
+                }
+                if let Some(bst) = app.stats_index.bst {
+                    stat_lines.push(Spans::from(Span::raw("")));
+                    stat_lines.push(Spans::from(Span::styled(
+                        format!("Dex BST {}-{}, avg {:.0}", bst.min, bst.max, bst.mean),
+                        dim,
+                    )));
                 }
 
-                let stats_para = Paragraph::new(stat_lines)
-                    .block(Block::default().borders(Borders::ALL).title("Stats"));
+                let stats_para = Paragraph::new(stat_lines).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!("Stats ({})", app.stat_scale.label())),
+                );
                 f.render_widget(stats_para, stats_rect);
 
-                let mut right_text: Vec<Spans> = Vec::new();
-                right_text.push(Spans::from(Span::styled(
-                    "Description:",
-                    Style::default().add_modifier(Modifier::BOLD),
-                )));
-                for line in text_to_lines(&p.description, 60) {
-                    right_text.push(Spans::from(Span::raw(line)));
-                }
-                right_text.push(Spans::from(Span::raw("")));
-                // AI Summary removed — leave space for potential replacement.
-                let right_para = Paragraph::new(right_text)
+                let right_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(3)])
+                    .split(bottom_chunks[1]);
+                let titles = DetailTab::ALL
+                    .iter()
+                    .map(|t| Spans::from(Span::raw(t.title())))
+                    .collect();
+                let tabs = Tabs::new(titles)
                     .block(Block::default().borders(Borders::ALL).title("Details"))
-                    .wrap(Wrap { trim: true });
-                f.render_widget(right_para, bottom_chunks[1]);
+                    .select(app.detail_tab.index())
+                    .highlight_style(
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                f.render_widget(tabs, right_chunks[0]);
+
+                let right_text = match app.detail_tab {
+                    DetailTab::Description => {
+                        let mut lines = search_match_lines(app, p);
+                        lines.extend(description_lines(p));
+                        lines
+                    }
+                    DetailTab::Encounters => encounter_lines(p, app.encounter_version.as_deref()),
+                    DetailTab::Calculator => calculator_lines(app, p),
+                    DetailTab::Radar => Vec::new(),
+                };
+                if app.detail_tab == DetailTab::Radar {
+                    f.render_widget(radar_widget(app, p, right_chunks[1]), right_chunks[1]);
+                } else {
+                    // Stop scrolling once the last line is at the bottom.
+                    let inner = right_chunks[1];
+                    let height = wrapped_height(&right_text, inner.width.saturating_sub(2));
+                    let max_scroll = height.saturating_sub(inner.height.saturating_sub(2));
+                    app.detail_scroll = app.detail_scroll.min(max_scroll);
+                    let right_para = Paragraph::new(right_text)
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(app.detail_tab.title()),
+                        )
+                        .wrap(Wrap { trim: true })
+                        .scroll((app.detail_scroll, 0));
+                    f.render_widget(right_para, right_chunks[1]);
+                }
 
                 Paragraph::new("").block(Block::default())
             } else {
@@ -499,32 +2229,171 @@ pub fn draw_ui(terminal: &mut Terminal<CrosstermBackend<Stdout>>, app: &mut App)
 
             // If help is requested, draw a centered help modal on top
             if app.show_help {
+                // Graphics would be drawn above the popup, so hide them.
+                app.graphic_request = None;
                 let area = f.size();
-                let popup = centered_rect(60, 40, area);
-                let mut help_lines: Vec<Spans> = Vec::new();
-                help_lines.push(Spans::from(Span::styled(
-                    "Keybindings",
-                    Style::default().add_modifier(Modifier::BOLD),
-                )));
-                help_lines.push(Spans::from(Span::raw("")));
-                help_lines.push(Spans::from(Span::raw("q       Quit")));
-                help_lines.push(Spans::from(Span::raw("/       Enter search mode")));
-                help_lines.push(Spans::from(Span::raw(
-                    "Enter/Esc  Finish or cancel search mode",
-                )));
-                help_lines.push(Spans::from(Span::raw("Up/Down Navigate list")));
-                help_lines.push(Spans::from(Span::raw("r       Refresh fetch (background)")));
-                help_lines.push(Spans::from(Span::raw("F1 / h  Toggle this help modal")));
+                let mut help_lines: Vec<Spans> = vec![
+                    Spans::from(Span::styled(
+                        "Keybindings",
+                        Style::default().add_modifier(Modifier::BOLD),
+                    )),
+                    Spans::from(Span::raw("")),
+                ];
+                for (key, action) in KEYBINDINGS {
+                    help_lines.push(Spans::from(Span::raw(format!("{:<11}{}", key, action))));
+                }
                 help_lines.push(Spans::from(Span::raw("")));
                 help_lines.push(Spans::from(Span::raw(
-                    "Use arrow keys to navigate and Enter to focus details.",
+                    "Up/Down and PgUp/PgDn scroll this help; Esc, h or F1 closes it.",
                 )));
 
+                // Fit the popup to the text, scrolling when the terminal is
+                // too small for it.
+                let text_w = help_lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16;
+                let w = (text_w + 4).min(area.width);
+                let text_h = wrapped_height(&help_lines, w.saturating_sub(2));
+                let h = (text_h + 2).min(area.height);
+                let popup = Rect::new(
+                    area.x + (area.width - w) / 2,
+                    area.y + (area.height - h) / 2,
+                    w,
+                    h,
+                );
+                let max_scroll = text_h.saturating_sub(h.saturating_sub(2));
+                app.help_scroll = app.help_scroll.min(max_scroll);
+                let title = if max_scroll > 0 {
+                    "Help (scroll for more)"
+                } else {
+                    "Help"
+                };
                 let help_para = Paragraph::new(help_lines)
-                    .block(Block::default().borders(Borders::ALL).title("Help"))
-                    .wrap(Wrap { trim: true });
+                    .block(Block::default().borders(Borders::ALL).title(title))
+                    .wrap(Wrap { trim: true })
+                    .scroll((app.help_scroll, 0));
+                f.render_widget(ratatui::widgets::Clear, popup);
                 f.render_widget(help_para, popup);
             }
-        })
-        .map(|_| ())
+
+            if app.preset_picker.is_some() {
+                app.graphic_request = None;
+                let popup = centered_rect(60, 50, f.size());
+                f.render_widget(ratatui::widgets::Clear, popup);
+                draw_preset_picker(f, app, popup);
+            }
+
+            if app.show_debug {
+                let area = f.size();
+                let w = 34.min(area.width);
+                let h = 9.min(area.height);
+                let popup = Rect::new(area.x + area.width - w, area.y + area.height - h, w, h);
+                f.render_widget(ratatui::widgets::Clear, popup);
+                f.render_widget(
+                    Paragraph::new(debug_lines(app))
+                        .block(Block::default().borders(Borders::ALL).title("Debug")),
+                    popup,
+                );
+                // Keep graphics from covering the overlay.
+                if let Some(req) = &app.graphic_request {
+                    if req.area.intersects(popup) {
+                        app.graphic_request = None;
+                    }
+                }
+            }
+        })?
+        .buffer
+        .clone();
+    if app.graphic_shown != app.graphic_request {
+        let seq = app.graphic_sequence();
+        graphics::sync(
+            terminal.backend_mut(),
+            &app.graphic_shown,
+            &app.graphic_request,
+            seq.as_deref(),
+            &frame,
+        )?;
+        // A graphic still being encoded is not on screen yet; leaving it
+        // unshown makes the next frame try again.
+        app.graphic_shown = match seq {
+            Some(_) => app.graphic_request.clone(),
+            None => None,
+        };
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with_history(entries: &[&str]) -> App {
+        let mut app = App::new(Vec::new());
+        app.search_history = entries.iter().map(|e| e.to_string()).collect();
+        app.start_search();
+        app
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            app.search_push(c);
+        }
+    }
+
+    #[test]
+    fn recall_steps_through_history_and_back_to_the_draft() {
+        let mut app = app_with_history(&["pika", "type:fire", "legendary"]);
+        type_text(&mut app, "dra");
+        let mut seen = Vec::new();
+        for older in [true, true, true, true, false, false, false, false] {
+            app.recall_history(older);
+            seen.push(app.search_query.clone());
+        }
+        assert_eq!(
+            seen,
+            [
+                "legendary",
+                "type:fire",
+                "pika",
+                "pika",
+                "type:fire",
+                "legendary",
+                "dra",
+                "dra"
+            ]
+        );
+    }
+
+    #[test]
+    fn editing_a_recalled_search_makes_it_the_draft() {
+        let mut app = app_with_history(&["pika", "type:fire"]);
+        app.recall_history(true);
+        type_text(&mut app, " gen:1");
+        app.recall_history(true);
+        assert_eq!(app.search_query, "type:fire");
+        app.recall_history(false);
+        assert_eq!(app.search_query, "type:fire gen:1");
+    }
+
+    #[test]
+    fn reopening_search_keeps_the_query_as_the_draft() {
+        let mut app = app_with_history(&["pika"]);
+        type_text(&mut app, "mew");
+        app.search_mode = false;
+        app.start_search();
+        assert_eq!(app.search_query, "mew");
+        app.recall_history(true);
+        assert_eq!(app.search_query, "pika");
+        app.recall_history(false);
+        assert_eq!(app.search_query, "mew");
+        app.clear_search();
+        assert_eq!(app.search_query, "");
+    }
+
+    #[test]
+    fn recall_without_history_does_nothing() {
+        let mut app = app_with_history(&[]);
+        type_text(&mut app, "mew");
+        app.recall_history(true);
+        app.recall_history(false);
+        assert_eq!(app.search_query, "mew");
+    }
 }
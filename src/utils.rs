@@ -4,12 +4,13 @@
 use crate::models::Pokemon;
 use std::error::Error;
 use std::fs;
+use std::path::PathBuf;
 
 /// Format a Pokémon `name` into a human-friendly form.
 ///
 /// Examples: `mr-mime` -> `Mr Mime`, `ho_oh` -> `Ho Oh`.
 pub fn format_name(name: &str) -> String {
-    let replaced = name.replace('-', " ").replace('_', " ");
+    let replaced = name.replace(['-', '_'], " ");
     let parts: Vec<String> = replaced
         .split_whitespace()
         .map(|w| {
@@ -25,6 +26,60 @@ pub fn format_name(name: &str) -> String {
     parts.join(" ")
 }
 
+/// Format a PokeAPI location area such as `kanto-route-1-area` as `Kanto Route 1`.
+pub fn format_location(area: &str) -> String {
+    format_name(area.strip_suffix("-area").unwrap_or(area))
+}
+
+/// Game versions in release order, used to sort encounter groups.
+pub const VERSION_ORDER: &[&str] = &[
+    "red",
+    "blue",
+    "yellow",
+    "gold",
+    "silver",
+    "crystal",
+    "ruby",
+    "sapphire",
+    "emerald",
+    "firered",
+    "leafgreen",
+    "diamond",
+    "pearl",
+    "platinum",
+    "heartgold",
+    "soulsilver",
+    "black",
+    "white",
+    "black-2",
+    "white-2",
+    "x",
+    "y",
+    "omega-ruby",
+    "alpha-sapphire",
+    "sun",
+    "moon",
+    "ultra-sun",
+    "ultra-moon",
+    "lets-go-pikachu",
+    "lets-go-eevee",
+    "sword",
+    "shield",
+    "brilliant-diamond",
+    "shining-pearl",
+    "legends-arceus",
+    "scarlet",
+    "violet",
+];
+
+/// Sort key for a game version; unknown versions sort last.
+pub fn version_rank(version: &str) -> usize {
+    VERSION_ORDER
+        .iter()
+        .position(|v| *v == version)
+        .unwrap_or(VERSION_ORDER.len())
+}
+
 pub fn text_to_lines(s: &str, width: usize) -> Vec<String> {
     // Wrap text into lines no longer than `width` (simple greedy algorithm).
     let mut lines = vec![];
@@ -45,6 +100,16 @@ pub fn text_to_lines(s: &str, width: usize) -> Vec<String> {
     lines
 }
 
+/// Directory for user settings such as saved searches:
+/// `$XDG_CONFIG_HOME/dextui`, or `~/.config/dextui` when that isn't set.
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(base.join("dextui"))
+}
+
 pub fn load_data(path: &str) -> Result<Vec<Pokemon>, Box<dyn Error>> {
     let data = fs::read_to_string(path)?;
     let v: Vec<Pokemon> = serde_json::from_str(&data)?;